@@ -0,0 +1,239 @@
+use std::mem;
+
+use crate::{Csv, Row, Cell};
+
+#[derive(Debug)]
+pub(crate) enum Edit {
+    Cell { row: usize, col: usize, cell: Cell },
+    Cells { row: usize, cells: Vec<Cell> },
+    InsertRow { index: usize, row: Row, eol: Option<&'static str> },
+    RemoveRow { index: usize },
+    Header { header: Option<(Row, &'static str)> },
+    Permute { order: Vec<usize> },
+    /// Changes no cells. Marks the column change of a transaction for `Csv::take_column_changes`.
+    Columns { change: ColumnChange },
+}
+
+/// Columns inserted, removed or reordered by undo or redo, so that settings kept by column can follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnChange {
+    Insert(usize),
+    Remove(usize),
+    /// The column now at `i` was at `order[i]`.
+    Reorder(Vec<usize>),
+}
+
+impl ColumnChange {
+    fn inverse(&self) -> Self {
+        match self {
+            Self::Insert(col) => Self::Remove(*col),
+            Self::Remove(col) => Self::Insert(*col),
+            Self::Reorder(order) => {
+                let mut inverse = vec![0; order.len()];
+                for (i, &j) in order.iter().enumerate() {
+                    inverse[j] = i;
+                }
+                Self::Reorder(inverse)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    /// Edits of each step with the revision they lead to.
+    undo: Vec<(u64, Vec<Edit>)>,
+    redo: Vec<(u64, Vec<Edit>)>,
+    last_revision: u64,
+    pending: Option<Vec<Edit>>,
+    changes: Vec<ColumnChange>,
+}
+
+impl History {
+    /// Revision of the last step that is not undone, `0` for the parsed document.
+    pub(crate) fn revision(&self) -> u64 {
+        self.undo.last().map(|(revision, _)| *revision).unwrap_or(0)
+    }
+
+    fn next_revision(&mut self) -> u64 {
+        self.last_revision += 1;
+        self.last_revision
+    }
+}
+
+impl Csv {
+    fn apply(&mut self, edit: Edit) -> Edit {
+        match edit {
+            Edit::Cell { row, col, mut cell } => {
+                mem::swap(&mut self.rows[row].0.cells[col], &mut cell);
+                Edit::Cell { row, col, cell }
+            }
+            Edit::Cells { row, mut cells } => {
+                mem::swap(&mut self.rows[row].0.cells, &mut cells);
+                Edit::Cells { row, cells }
+            }
+            Edit::InsertRow { index, row, eol } => {
+                self.rows.insert(index, (row, eol));
+                Edit::RemoveRow { index }
+            }
+            Edit::RemoveRow { index } => {
+                let (row, eol) = self.rows.remove(index);
+                Edit::InsertRow { index, row, eol }
+            }
+            Edit::Header { mut header } => {
+                mem::swap(&mut self.header, &mut header);
+                Edit::Header { header }
+            }
+            Edit::Permute { order } => {
+                let mut rows = order.iter()
+                    .map(|&i| mem::replace(&mut self.rows[i].0, Row { cells: vec![] }))
+                    .collect::<Vec<_>>();
+                for (i, row) in rows.drain(..).enumerate() {
+                    self.rows[i].0 = row;
+                }
+                let mut inverse = vec![0; order.len()];
+                for (i, &j) in order.iter().enumerate() {
+                    inverse[j] = i;
+                }
+                Edit::Permute { order: inverse }
+            }
+            Edit::Columns { change } => Edit::Columns { change: change.inverse() },
+        }
+    }
+
+    fn revert(&mut self, edits: Vec<Edit>) -> Vec<Edit> {
+        edits.into_iter().rev().map(|edit| {
+            if let Edit::Columns { change } = &edit {
+                self.history.changes.push(change.clone());
+            }
+            self.apply(edit)
+        }).collect()
+    }
+
+    /// Column changes made by undo and redo since the last call, in order.
+    pub fn take_column_changes(&mut self) -> Vec<ColumnChange> {
+        mem::take(&mut self.history.changes)
+    }
+
+    pub(crate) fn record(&mut self, edit: Edit) {
+        let inverse = self.apply(edit);
+        self.history.redo.clear();
+        if let Some(pending) = &mut self.history.pending {
+            pending.push(inverse);
+        } else {
+            let revision = self.history.next_revision();
+            self.history.undo.push((revision, vec![inverse]));
+        }
+    }
+
+    pub fn transaction<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        if self.history.pending.is_some() {
+            return f(self)
+        }
+        self.history.pending = Some(vec![]);
+        let result = f(self);
+        if let Some(edits) = self.history.pending.take() {
+            if !edits.is_empty() {
+                let revision = self.history.next_revision();
+                self.history.undo.push((revision, edits));
+            }
+        }
+        result
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        if let Some((revision, edits)) = self.history.undo.pop() {
+            let edits = self.revert(edits);
+            self.history.redo.push((revision, edits));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if let Some((revision, edits)) = self.history.redo.pop() {
+            let edits = self.revert(edits);
+            self.history.undo.push((revision, edits));
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_undoes_as_one_step() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("1,2\n3,4\n", false)?;
+        csv.transaction(|csv| {
+            csv.set_val(0, 0, "a");
+            csv.remove_row(1);
+        });
+        assert_eq!(csv.to_string(), "a,2\n");
+        assert!(csv.undo());
+        assert_eq!(csv.to_string(), "1,2\n3,4\n");
+        assert!(!csv.can_undo());
+        assert!(csv.redo());
+        assert_eq!(csv.to_string(), "a,2\n");
+        assert!(!csv.can_redo());
+        Ok(())
+    }
+
+    #[test]
+    fn new_edit_clears_redo() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("1\n", false)?;
+        csv.set_val(0, 0, "2");
+        csv.undo();
+        assert!(csv.can_redo());
+        csv.set_val(0, 0, "3");
+        assert!(!csv.can_redo());
+        assert!(!csv.redo());
+        Ok(())
+    }
+
+    #[test]
+    fn revision_follows_history_position() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("1\n", false)?;
+        assert_eq!(csv.revision(), 0);
+        csv.set_val(0, 0, "2");
+        let edited = csv.revision();
+        assert_ne!(edited, 0);
+        csv.undo();
+        assert_eq!(csv.revision(), 0);
+        csv.redo();
+        assert_eq!(csv.revision(), edited);
+        csv.undo();
+        csv.set_val(0, 0, "2");
+        assert_ne!(csv.revision(), edited);
+        Ok(())
+    }
+
+    #[test]
+    fn column_changes_are_reported_on_undo_and_redo() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("a,b,c\n", false)?;
+        csv.insert_col(1);
+        csv.reorder_cols(&[2, 0, 1, 3]);
+        assert!(csv.take_column_changes().is_empty());
+        csv.undo();
+        assert_eq!(csv.take_column_changes(), vec![ColumnChange::Reorder(vec![1, 2, 0, 3])]);
+        csv.undo();
+        assert_eq!(csv.take_column_changes(), vec![ColumnChange::Remove(1)]);
+        assert_eq!(csv.to_string(), "a,b,c\n");
+        csv.redo();
+        assert_eq!(csv.take_column_changes(), vec![ColumnChange::Insert(1)]);
+        assert!(csv.take_column_changes().is_empty());
+        Ok(())
+    }
+}
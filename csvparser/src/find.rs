@@ -0,0 +1,107 @@
+use std::borrow::Cow;
+
+use regex::{NoExpand, Regex, RegexBuilder};
+
+use crate::Csv;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Literal,
+    CaseInsensitive,
+    WholeCell,
+    Regex,
+}
+
+#[derive(Debug, Clone)]
+pub struct Finder {
+    regex: Regex,
+    expand: bool,
+}
+
+impl Finder {
+    pub fn new(pattern: &str, mode: Mode) -> Result<Self, regex::Error> {
+        let regex = match mode {
+            Mode::Literal => RegexBuilder::new(&regex::escape(pattern)).build()?,
+            Mode::CaseInsensitive => RegexBuilder::new(&regex::escape(pattern)).case_insensitive(true).build()?,
+            Mode::WholeCell => RegexBuilder::new(&format!("^{}$", regex::escape(pattern))).build()?,
+            Mode::Regex => RegexBuilder::new(pattern).build()?,
+        };
+        Ok(Self { regex, expand: mode == Mode::Regex })
+    }
+
+    pub fn is_match(&self, val: &str) -> bool {
+        self.regex.is_match(val)
+    }
+
+    /// Number of matches in `val`.
+    pub fn count(&self, val: &str) -> usize {
+        self.regex.find_iter(val).count()
+    }
+
+    /// Replaces every match in `val`. `$1` style references are expanded in regex mode only.
+    pub fn replace<'a>(&self, val: &'a str, replacement: &str) -> Cow<'a, str> {
+        if self.expand {
+            self.regex.replace_all(val, replacement)
+        } else {
+            self.regex.replace_all(val, NoExpand(replacement))
+        }
+    }
+}
+
+impl Csv {
+    /// Cells matching `finder` in row-major order.
+    pub fn find(&self, finder: &Finder, rows: &[usize], cols: &[usize]) -> Vec<(usize, usize)> {
+        rows.iter().flat_map(|&row| cols.iter().map(move |&col| (row, col)))
+            .filter(|&(row, col)| self.val(row, col).map(|val| finder.is_match(val)).unwrap_or(false))
+            .collect()
+    }
+
+    /// Replaces all matches as one edit. Returns the number of replaced matches.
+    pub fn replace_all(&mut self, finder: &Finder, replacement: &str, rows: &[usize], cols: &[usize]) -> usize {
+        let matches = self.find(finder, rows, cols);
+        let mut count = 0;
+        self.transaction(|csv| {
+            for &(row, col) in &matches {
+                let val = csv.val(row, col).map(|val| (finder.count(val), finder.replace(val, replacement).into_owned()));
+                if let Some((n, val)) = val {
+                    count += n;
+                    csv.set_val(row, col, val);
+                }
+            }
+        });
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_all_counts_matches() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("aaa,b\na,c\n", false)?;
+        let finder = Finder::new("a", Mode::Literal)?;
+        assert_eq!(csv.replace_all(&finder, "x", &[0, 1], &[0, 1]), 4);
+        assert_eq!(csv.to_string(), "xxx,b\nx,c\n");
+        assert!(csv.undo());
+        assert_eq!(csv.to_string(), "aaa,b\na,c\n");
+        Ok(())
+    }
+
+    #[test]
+    fn replace_all_respects_rows_and_cols() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("a,a\na,a\n", false)?;
+        let finder = Finder::new("A", Mode::CaseInsensitive)?;
+        assert_eq!(csv.replace_all(&finder, "x", &[1], &[0]), 1);
+        assert_eq!(csv.to_string(), "a,a\nx,a\n");
+        Ok(())
+    }
+
+    #[test]
+    fn regex_replacement_expands_groups() -> anyhow::Result<()> {
+        let finder = Finder::new("(\\d+)-(\\d+)", Mode::Regex)?;
+        assert_eq!(finder.replace("1-2", "$2-$1"), "2-1");
+        assert_eq!(Finder::new("a", Mode::WholeCell)?.count("aa"), 0);
+        Ok(())
+    }
+}
@@ -50,13 +50,15 @@ impl<S> fmt::Display for Token<S> where S: fmt::Debug {
 pub(crate) struct Lexer<'input> {
     cursor: Peekable<CharIndices<'input>>,
     input: &'input str,
+    delimiter: char,
 }
 
 impl<'input> Lexer<'input> {
-    pub(crate) fn new(input: &'input str) -> Self {
+    pub(crate) fn new(input: &'input str, delimiter: char) -> Self {
         Self {
             cursor: input.char_indices().peekable(),
             input,
+            delimiter,
         }
     }
 }
@@ -66,7 +68,7 @@ impl<'input> Iterator for Lexer<'input> {
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.cursor.next() {
-            Some((i, '\x2C')) => Some(Ok((i, Token::COMMA, i + 1))),
+            Some((i, c)) if c == self.delimiter => Some(Ok((i, Token::COMMA, i + c.len_utf8()))),
             Some((i, '\x0D')) => {
                 if let Some((_, '\x0A')) = self.cursor.peek() {
                     self.cursor.next();
@@ -90,7 +92,7 @@ impl<'input> Iterator for Lexer<'input> {
             Some((i, _)) => {
                 while let Some((j, x)) = self.cursor.peek() {
                     match x {
-                        '\x2C' | '\x0D' | '\x22' | '\x0A' => {
+                        x if *x == self.delimiter || ['\x0D', '\x22', '\x0A'].contains(x) => {
                             return Some(Ok((i, Token::TEXTDATA(&self.input[i..*j]), *j)))
                         }
                         _ => {
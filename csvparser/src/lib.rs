@@ -1,8 +1,17 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt;
+use std::ops::Range;
 
 use lalrpop_util::lalrpop_mod;
 
+use edit::{Edit, History};
+pub use edit::ColumnChange;
+pub use find::{Finder, Mode};
+
 mod lex;
+mod edit;
+mod find;
 lalrpop_mod!(csv);
 
 #[derive(Debug, thiserror::Error)]
@@ -11,17 +20,27 @@ pub enum ParseError {
     ParseError(#[from] lalrpop_util::ParseError<usize, lex::Token<String>, String>),
 }
 
+pub fn quote(val: &str, delimiter: char) -> Cow<'_, str> {
+    if val.contains(&[delimiter, '\r', '"', '\n'][..]) {
+        Cow::Owned(format!("\"{}\"", val.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(val)
+    }
+}
+
 #[derive(Debug)]
 pub struct Csv {
     header: Option<(Row, &'static str)>,
     rows: Vec<(Row, Option<&'static str>)>,
+    delimiter: char,
+    history: History,
 }
 
 impl Csv {
     fn new(v: Vec<(Row, &'static str)>, r: Row, eol: Option<&'static str>) -> Self {
         let mut rows = v.into_iter().map(|(r, l)| (r, Some(l))).collect::<Vec<_>>();
         rows.push((r, eol));
-        Self { header: None, rows }
+        Self { header: None, rows, delimiter: ',', history: History::default() }
     }
 
     fn new_with_header(
@@ -32,39 +51,278 @@ impl Csv {
 
         let mut rows = v.into_iter().map(|(r, l)| (r, Some(l))).collect::<Vec<_>>();
         rows.push((r, eol));
-        Self { header: Some(h), rows }
+        Self { header: Some(h), rows, delimiter: ',', history: History::default() }
+    }
+
+    fn new_header_only(h: Row, eol: &'static str) -> Self {
+        Self { header: Some((h, eol)), rows: vec![], delimiter: ',', history: History::default() }
     }
 
     pub fn parse<'input>(input: &'input str, header: bool) -> Result<Csv, ParseError> {
-        if header {
-            Self::parse_with_header(input)
+        Self::parse_with_delimiter(input, header, ',')
+    }
+
+    pub fn parse_with_delimiter(input: &str, header: bool, delimiter: char) -> Result<Csv, ParseError> {
+        let lexer = lex::Lexer::new(input, delimiter);
+        let mut result = if header {
+            csv::CsvWithHeaderParser::new().parse(input, lexer)
         } else {
-            Self::parse_without_header(input)
-        }
+            csv::CsvParser::new().parse(input, lexer)
+        }.map_err(|e|e.map_token(lex::Token::to_owned))?;
+        result.delimiter = delimiter;
+        Ok(result)
     }
 
     pub fn parse_without_header<'input>(input: &'input str) -> Result<Csv, ParseError> {
-        let lexer = lex::Lexer::new(&input);
-        let result = csv::CsvParser::new()
-            .parse(&input, lexer).map_err(|e|e.map_token(lex::Token::to_owned))?;
-        Ok(result)
+        Self::parse_with_delimiter(input, false, ',')
     }
 
     pub fn parse_with_header<'input>(input: &'input str) -> Result<Csv, ParseError> {
-        let lexer = lex::Lexer::new(&input);
-        let result = csv::CsvWithHeaderParser::new()
-            .parse(&input, lexer).map_err(|e|e.map_token(lex::Token::to_owned))?;
-        Ok(result)
+        Self::parse_with_delimiter(input, true, ',')
+    }
+
+    pub fn sniff_delimiter(input: &str) -> char {
+        let first_line = input.lines().next().unwrap_or("");
+        if first_line.contains('\t') {
+            '\t'
+        } else {
+            ','
+        }
+    }
+
+    pub fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    pub fn eol(&self) -> &'static str {
+        self.header.as_ref().map(|(_, eol)| *eol)
+            .or_else(|| self.rows.iter().find_map(|(_, eol)| *eol))
+            .unwrap_or("\r\n")
     }
 
     pub fn insert_row(&mut self, index: usize) {
-        let cells = (0..self.max_cols()).map(|_| Cell::new(false, &[][..])).into_iter().collect();
+        let cells = (0..self.max_cols()).map(|_| Cell::new(false, &[][..])).collect();
         let row = Row { cells };
-        self.rows.insert(index, (row, Some("\r\n")));
+        let eol = Some(self.eol());
+        self.record(Edit::InsertRow { index, row, eol });
     }
 
     pub fn remove_row(&mut self, index: usize) {
-        self.rows.remove(index);
+        self.record(Edit::RemoveRow { index });
+    }
+
+    pub fn insert_rows(&mut self, index: usize, count: usize) {
+        self.transaction(|csv| {
+            for _ in 0..count {
+                csv.insert_row(index);
+            }
+        })
+    }
+
+    pub fn remove_rows(&mut self, rows: &[usize]) {
+        let mut rows = rows.to_vec();
+        rows.sort_unstable();
+        rows.dedup();
+        self.transaction(|csv| {
+            for &index in rows.iter().rev() {
+                csv.remove_row(index);
+            }
+        })
+    }
+
+    pub fn duplicate_rows(&mut self, range: Range<usize>) {
+        self.transaction(|csv| {
+            for (i, index) in range.clone().enumerate() {
+                let (row, eol) = csv.rows[index].clone();
+                csv.record(Edit::InsertRow { index: range.end + i, row, eol });
+            }
+        })
+    }
+
+    /// Moves the rows in `range` so that they start at `index` of the remaining rows.
+    pub fn move_rows(&mut self, range: Range<usize>, index: usize) {
+        self.transaction(|csv| {
+            let mut rows = vec![];
+            for i in range.rev() {
+                let (row, eol) = csv.rows[i].clone();
+                csv.remove_row(i);
+                rows.push((row, eol));
+            }
+            for (row, eol) in rows {
+                csv.record(Edit::InsertRow { index, row, eol });
+            }
+        })
+    }
+
+    /// Pastes one line of `values` into each of `rows`, one value into each of `cols`.
+    /// Lines and values past the given rows and columns go to new rows and columns at the end.
+    pub fn paste(&mut self, rows: &[usize], cols: &[usize], values: &[Vec<String>]) {
+        self.transaction(|csv| {
+            let end = csv.rows();
+            let rows = (0..values.len())
+                .map(|r| rows.get(r).copied().unwrap_or_else(|| end + r - rows.len()))
+                .collect::<Vec<_>>();
+            let max_cols = csv.max_cols();
+            let cols = (0..values.iter().map(Vec::len).max().unwrap_or(0))
+                .map(|c| cols.get(c).copied().unwrap_or_else(|| max_cols + c - cols.len()))
+                .collect::<Vec<_>>();
+            while rows.iter().any(|&row| csv.rows() <= row) {
+                csv.insert_row(csv.rows());
+            }
+            for (&row, line) in rows.iter().zip(values) {
+                let len = cols.iter().take(line.len()).map(|col| col + 1).max().unwrap_or(0);
+                let cells = &csv.rows[row].0.cells;
+                if cells.len() < len {
+                    let mut cells = cells.clone();
+                    cells.resize_with(len, || Cell::new(false, &[][..]));
+                    csv.record(Edit::Cells { row, cells });
+                }
+                for (&col, val) in cols.iter().zip(line) {
+                    csv.set_val(row, col, val);
+                }
+            }
+        })
+    }
+
+    pub fn insert_col(&mut self, index: usize) {
+        self.transaction(|csv| {
+            csv.record(Edit::Columns { change: ColumnChange::Insert(index) });
+            if let Some((header, eol)) = &csv.header {
+                if header.cells.len() >= index {
+                    let mut header = header.clone();
+                    header.cells.insert(index, Cell::new(false, &[][..]));
+                    let header = Some((header, *eol));
+                    csv.record(Edit::Header { header });
+                }
+            }
+            for row in 0..csv.rows() {
+                let cells = &csv.rows[row].0.cells;
+                if cells.len() >= index {
+                    let mut cells = cells.clone();
+                    cells.insert(index, Cell::new(false, &[][..]));
+                    csv.record(Edit::Cells { row, cells });
+                }
+            }
+        })
+    }
+
+    pub fn remove_col(&mut self, index: usize) {
+        self.transaction(|csv| {
+            csv.record(Edit::Columns { change: ColumnChange::Remove(index) });
+            if let Some((header, eol)) = &csv.header {
+                if header.cells.len() > index {
+                    let mut header = header.clone();
+                    header.cells.remove(index);
+                    let header = Some((header, *eol));
+                    csv.record(Edit::Header { header });
+                }
+            }
+            for row in 0..csv.rows() {
+                let cells = &csv.rows[row].0.cells;
+                if cells.len() > index {
+                    let mut cells = cells.clone();
+                    cells.remove(index);
+                    csv.record(Edit::Cells { row, cells });
+                }
+            }
+        })
+    }
+
+    /// Copy with the columns rearranged in `order`. Columns missing from `order` are dropped.
+    pub fn with_col_order(&self, order: &[usize]) -> Csv {
+        Csv {
+            header: self.header.as_ref().map(|(header, eol)| (header.reorder(order), *eol)),
+            rows: self.rows.iter().map(|(row, eol)| (row.reorder(order), *eol)).collect(),
+            delimiter: self.delimiter,
+            history: History::default(),
+        }
+    }
+
+    /// Sets a header caption, creating the header row if there is none.
+    /// Moves the columns into `order` as one edit.
+    pub fn reorder_cols(&mut self, order: &[usize]) {
+        self.transaction(|csv| {
+            csv.record(Edit::Columns { change: ColumnChange::Reorder(order.to_vec()) });
+            if let Some((header, eol)) = &csv.header {
+                let header = Some((header.reorder(order), *eol));
+                csv.record(Edit::Header { header });
+            }
+            for row in 0..csv.rows() {
+                let cells = csv.rows[row].0.reorder(order).cells;
+                csv.record(Edit::Cells { row, cells });
+            }
+        })
+    }
+
+    pub fn set_header<S: ToString>(&mut self, col: usize, val: S) {
+        let (mut header, eol) = self.header.clone().unwrap_or_else(|| {
+            let cells = (0..self.max_cols()).map(|_| Cell::new(false, &[][..])).collect();
+            (Row { cells }, self.eol())
+        });
+        if header.cells.len() <= col {
+            header.cells.resize_with(col + 1, || Cell::new(false, &[][..]));
+        }
+        header.cells[col].set_val(val.to_string(), self.delimiter);
+        self.record(Edit::Header { header: Some((header, eol)) });
+    }
+
+    /// Turns the row at `index` into the header, demoting the current header to the first row.
+    pub fn promote_to_header(&mut self, index: usize) {
+        if index >= self.rows() {
+            return
+        }
+        self.transaction(|csv| {
+            let index = if csv.demote_header() { index + 1 } else { index };
+            let (row, eol) = csv.rows[index].clone();
+            let eol = eol.unwrap_or_else(|| csv.eol());
+            csv.remove_row(index);
+            csv.record(Edit::Header { header: Some((row, eol)) });
+        })
+    }
+
+    /// Turns the header into the first row. Returns false when there is no header.
+    pub fn demote_header(&mut self) -> bool {
+        if let Some((row, eol)) = self.header.clone() {
+            self.transaction(|csv| {
+                csv.record(Edit::Header { header: None });
+                csv.record(Edit::InsertRow { index: 0, row, eol: Some(eol) });
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Sorts the rows by the values of `col`, comparing them as numbers when `numeric`.
+    pub fn sort_by_col(&mut self, col: usize, ascending: bool, numeric: bool) {
+        let mut order = (0..self.rows()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (self.val(a, col).unwrap_or(""), self.val(b, col).unwrap_or(""));
+            let ordering = match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+                (Ok(a), Ok(b)) if numeric => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (Ok(_), Err(_)) if numeric => Ordering::Less,
+                (Err(_), Ok(_)) if numeric => Ordering::Greater,
+                _ => a.cmp(b),
+            };
+            if ascending { ordering } else { ordering.reverse() }
+        });
+        if order.iter().enumerate().any(|(i, &j)| i != j) {
+            self.record(Edit::Permute { order });
+        }
+    }
+
+    /// Identifies the position in the edit history. Undoing back to a position gives its revision again.
+    pub fn revision(&self) -> u64 {
+        self.history.revision()
+    }
+
+    pub fn has_header(&self) -> bool {
+        self.header.is_some()
+    }
+
+    /// Whether the first cell starts with a byte order mark, which is written back as is.
+    pub fn has_bom(&self) -> bool {
+        self.header(0).or_else(|| self.val(0, 0)).map(|val| val.starts_with('\u{feff}')).unwrap_or(false)
     }
 
     pub fn header(&self, col: usize) -> Option<&str> {
@@ -75,18 +333,29 @@ impl Csv {
         self.rows.len()
     }
 
+    /// Widest of the header and the rows.
     pub fn max_cols(&self) -> usize {
-        self.rows.iter().map(|(r, _)| r.cells.len()).max().unwrap_or(0)
+        self.header.iter().map(|(h, _)| h.cells.len())
+            .chain(self.rows.iter().map(|(r, _)| r.cells.len()))
+            .max().unwrap_or(0)
     }
 
     pub fn vals(&self, row: usize) -> impl Iterator<Item=&str> + '_ {
         self.rows.get(row).map(|(r, _)| r.cells.iter().map(Cell::val)).into_iter().flatten()
     }
 
+    pub fn val(&self, row: usize, col: usize) -> Option<&str> {
+        self.rows.get(row).and_then(|(r, _)| r.cells.get(col)).map(Cell::val)
+    }
+
+    /// Returns whether the value changed. Writing the same value records no edit.
     pub fn set_val<S:ToString>(&mut self, row: usize, col: usize, val: S) -> bool {
-        let maybe_cell = self.rows.get_mut(row).and_then(|(r, _)| r.cells.get_mut(col));
+        let val = val.to_string();
+        let maybe_cell = self.rows.get(row).and_then(|(r, _)| r.cells.get(col)).filter(|cell| cell.val() != val);
         if let Some(cell) = maybe_cell {
-            cell.set_val(val.to_string());
+            let mut cell = cell.clone();
+            cell.set_val(val, self.delimiter);
+            self.record(Edit::Cell { row, col, cell });
             true
         } else {
             false
@@ -101,21 +370,24 @@ impl Csv {
 impl fmt::Display for Csv {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some((header, eol)) = &self.header {
-            write!(f, "{}{}", header, eol)?;
+            header.write(f, self.delimiter)?;
+            write!(f, "{}", eol)?;
         };
 
-        for (row, eol) in &self.rows {
-            if let Some(eol) = eol {
-                write!(f, "{}{}", row, eol)?;
-            } else {
-                write!(f, "{}", row)?;
+        let last = self.rows.len().saturating_sub(1);
+        for (i, (row, eol)) in self.rows.iter().enumerate() {
+            row.write(f, self.delimiter)?;
+            match eol {
+                Some(eol) => write!(f, "{}", eol)?,
+                None if i < last => write!(f, "{}", self.eol())?,
+                None => {}
             }
         }
         Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Row {
     cells: Vec<Cell>,
 }
@@ -127,6 +399,16 @@ impl Row {
         Self { cells }
     }
 
+    /// Cells in `order`. Cells a short row lacks become empty, except at the end of the row.
+    fn reorder(&self, order: &[usize]) -> Self {
+        let mut cells = order.iter().map(|&col| self.cells.get(col).cloned()).collect::<Vec<_>>();
+        while let Some(None) = cells.last() {
+            cells.pop();
+        }
+        let cells = cells.into_iter().map(|cell| cell.unwrap_or_else(|| Cell::new(false, &[][..]))).collect();
+        Self { cells }
+    }
+
     /*
     pub fn add_col<S: ToString>(&mut self, val: S) -> &mut Cell {
         let val = val.to_string();
@@ -141,8 +423,8 @@ impl Row {
     */
 }
 
-impl fmt::Display for Row {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Row {
+    fn write(&self, f: &mut fmt::Formatter<'_>, delimiter: char) -> fmt::Result {
         let mut iter = self.cells.iter();
         if let Some(cell) = iter.next() {
             write!(f, "{}", cell)?;
@@ -150,13 +432,19 @@ impl fmt::Display for Row {
             return Ok(())
         }
         while let Some(cell) = iter.next() {
-            write!(f, ",{}", cell)?;
+            write!(f, "{}{}", delimiter, cell)?;
         }
         Ok(())
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, ',')
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Cell {
     val: String,
     quoted: bool,
@@ -174,9 +462,9 @@ impl Cell {
         &self.val
     }
 
-    fn set_val<S: ToString>(&mut self, val: S) {
+    fn set_val<S: ToString>(&mut self, val: S, delimiter: char) {
         let val = val.to_string();
-        if !self.quoted && val.contains(&[',', '\r', '"', '\n'][..]) {
+        if !self.quoted && val.contains(&[delimiter, '\r', '"', '\n'][..]) {
             self.quoted = true
         }
         self.val = val;
@@ -192,3 +480,111 @@ impl fmt::Display for Cell {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vals(csv: &Csv) -> Vec<Vec<&str>> {
+        (0..csv.rows()).map(|row| csv.vals(row).collect()).collect()
+    }
+
+    #[test]
+    fn display_round_trips_line_endings() -> anyhow::Result<()> {
+        for input in &["a,b\r\n1,2\r\n3,4\r\n", "a,b\n1,2\n3,4", "a,\"b\nc\"\n1,2\n"] {
+            assert_eq!(Csv::parse(input, true)?.to_string(), *input);
+            assert_eq!(Csv::parse(input, false)?.to_string(), *input);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn display_ends_inserted_rows_with_file_eol() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("a,b\r\n1,2", true)?;
+        csv.insert_row(1);
+        csv.set_val(1, 0, "3");
+        assert_eq!(csv.to_string(), "a,b\r\n1,2\r\n3,\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn paste_grows_rows_and_cols() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("1,2\n3,4\n", false)?;
+        let values = vec![
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            vec!["d".to_string(), "e".to_string(), "f".to_string()],
+        ];
+        csv.paste(&[1], &[1], &values);
+        assert_eq!(vals(&csv), vec![vec!["1", "2"], vec!["3", "a", "b", "c"], vec!["", "d", "e", "f"]]);
+        assert!(csv.undo());
+        assert_eq!(vals(&csv), vec![vec!["1", "2"], vec!["3", "4"]]);
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_and_move_rows() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("a\nb\nc\nd\n", false)?;
+        csv.duplicate_rows(1..3);
+        assert_eq!(csv.cols(0).collect::<Vec<_>>(), vec!["a", "b", "c", "b", "c", "d"]);
+        csv.undo();
+        csv.move_rows(1..3, 0);
+        assert_eq!(csv.cols(0).collect::<Vec<_>>(), vec!["b", "c", "a", "d"]);
+        csv.undo();
+        csv.move_rows(0..2, 2);
+        assert_eq!(csv.cols(0).collect::<Vec<_>>(), vec!["c", "d", "a", "b"]);
+        csv.undo();
+        assert_eq!(csv.cols(0).collect::<Vec<_>>(), vec!["a", "b", "c", "d"]);
+        Ok(())
+    }
+
+    #[test]
+    fn promote_and_demote_header() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("a,b\n1,2\n", true)?;
+        csv.promote_to_header(0);
+        assert_eq!(csv.header(0), Some("1"));
+        assert_eq!(csv.rows(), 1);
+        assert_eq!(csv.to_string(), "1,2\na,b\n");
+        assert!(csv.demote_header());
+        assert!(!csv.has_header());
+        assert_eq!(csv.to_string(), "1,2\na,b\n");
+        assert!(!csv.demote_header());
+        csv.undo();
+        csv.undo();
+        assert_eq!(csv.header(0), Some("a"));
+        assert_eq!(csv.to_string(), "a,b\n1,2\n");
+        Ok(())
+    }
+
+    #[test]
+    fn header_only_file_round_trips() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("a,b\n", false)?;
+        csv.promote_to_header(0);
+        assert_eq!(csv.rows(), 0);
+        let written = csv.to_string();
+        let parsed = Csv::parse(&written, true)?;
+        assert_eq!(parsed.header(1), Some("b"));
+        assert_eq!(parsed.rows(), 0);
+        assert_eq!(parsed.to_string(), written);
+        Ok(())
+    }
+
+    #[test]
+    fn with_col_order_keeps_ragged_rows() -> anyhow::Result<()> {
+        let csv = Csv::parse("a,b,c\n1\n2,3\n", true)?;
+        let ordered = csv.with_col_order(&[2, 0, 1]);
+        assert_eq!(ordered.header(0), Some("c"));
+        assert_eq!(vals(&ordered), vec![vec!["", "1"], vec!["", "2", "3"]]);
+        assert_eq!(csv.header(0), Some("a"));
+        Ok(())
+    }
+
+    #[test]
+    fn set_val_skips_unchanged_values() -> anyhow::Result<()> {
+        let mut csv = Csv::parse("1,2\n", false)?;
+        assert!(!csv.set_val(0, 0, "1"));
+        assert!(!csv.can_undo());
+        assert!(csv.set_val(0, 0, "3"));
+        assert!(csv.can_undo());
+        Ok(())
+    }
+}
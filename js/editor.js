@@ -0,0 +1,50 @@
+import { columns } from "cheetah-grid";
+
+// All editors share the grid input, so they share one list of suggestions too.
+const CHOICES_ID = "app-cell-editor-choices";
+
+function setChoices(input, choices) {
+  if (!choices) {
+    input.removeAttribute("list");
+    return;
+  }
+  let list = document.getElementById(CHOICES_ID);
+  if (!list) {
+    list = document.createElement("datalist");
+    list.id = CHOICES_ID;
+    document.body.appendChild(list);
+  }
+  list.replaceChildren(...choices.map((value) => {
+    const option = document.createElement("option");
+    option.value = value;
+    return option;
+  }));
+  input.setAttribute("list", CHOICES_ID);
+}
+
+// Inline input editor that lets `openText` take over cells it edits elsewhere
+// and suggests `choices` while still accepting any text.
+export class CellEditor extends columns.action.InlineInputEditor {
+  constructor(option = {}) {
+    super(option);
+    this.openText = null;
+    this.choices = option.choices || null;
+  }
+
+  onOpenCellInternal(grid, cell) {
+    if (!this.openText || !this.openText(cell.col, cell.row)) {
+      super.onOpenCellInternal(grid, cell);
+    }
+  }
+
+  onInputCellInternal(grid, cell, inputValue) {
+    if (!this.openText || !this.openText(cell.col, cell.row)) {
+      super.onInputCellInternal(grid, cell, inputValue);
+    }
+  }
+
+  onSetInputAttrsInternal(grid, cell, input) {
+    super.onSetInputAttrsInternal(grid, cell, input);
+    setChoices(input, this.choices);
+  }
+}
@@ -0,0 +1,60 @@
+import { columns } from "cheetah-grid";
+
+// cheetah-grid draws text inset by 2px from the cell edge.
+const OFFSET = 2;
+
+const LINE_BREAK = /\r\n|\r|\n/g;
+
+function drawHighlight(query, value, context, style, grid) {
+  const lower = value.toLowerCase();
+  const needle = query.toLowerCase();
+  let index = lower.indexOf(needle);
+  if (index < 0) {
+    return;
+  }
+
+  const ctx = context.getContext();
+  const rect = context.getRect();
+  ctx.save();
+  ctx.font = (style && style.font) || grid.font || ctx.font;
+  const total = ctx.measureText(value).width;
+  const left = (style && style.textAlign) === "right"
+    ? rect.right - OFFSET - total
+    : rect.left + OFFSET;
+
+  ctx.beginPath();
+  ctx.rect(rect.left, rect.top, rect.width, rect.height);
+  ctx.clip();
+  ctx.globalCompositeOperation = "multiply";
+  ctx.fillStyle = "#ffeb3b";
+  while (index >= 0) {
+    const x = left + ctx.measureText(value.slice(0, index)).width;
+    const width = ctx.measureText(value.slice(index, index + needle.length)).width;
+    ctx.fillRect(x, rect.top + 2, width, rect.height - 4);
+    index = lower.indexOf(needle, index + needle.length);
+  }
+  ctx.restore();
+}
+
+function highlighted(Base) {
+  return class extends Base {
+    constructor(option) {
+      super(option);
+      this.query = "";
+    }
+
+    drawInternal(value, context, style, helper, grid, info) {
+      // line breaks are drawn as a glyph to keep the row on one line
+      if (typeof value === "string") {
+        value = value.replace(LINE_BREAK, "\u21b5");
+      }
+      super.drawInternal(value, context, style, helper, grid, info);
+      if (this.query && value != null) {
+        drawHighlight(this.query, String(value), context, style, grid);
+      }
+    }
+  };
+}
+
+export const HighlightColumn = highlighted(columns.type.Column);
+export const HighlightNumberColumn = highlighted(columns.type.NumberColumn);
@@ -0,0 +1,240 @@
+use std::collections::BTreeSet;
+
+use wasm_bindgen::JsValue;
+use gloo::storage::{LocalStorage, Storage};
+
+/// Most distinct values a column may have to be inferred as a choice.
+const MAX_CHOICES: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColumnType {
+    Text,
+    Number,
+    Boolean,
+    Choice,
+    Date,
+}
+
+impl ColumnType {
+    pub(crate) fn from_action(action: &str) -> Option<Self> {
+        match action {
+            "type_text" => Some(Self::Text),
+            "type_number" => Some(Self::Number),
+            "type_boolean" => Some(Self::Boolean),
+            "type_choice" => Some(Self::Choice),
+            "type_date" => Some(Self::Date),
+            _ => None,
+        }
+    }
+
+    /// Empty values are left out, except that a check box cannot hold them.
+    /// Choices are inferred for a few distinct values that each repeat on average.
+    pub(crate) fn infer<'a>(vals: impl Iterator<Item=&'a str>) -> Self {
+        let (vals, empty) = vals.fold((vec![], false), |(mut vals, empty), val| {
+            if !val.is_empty() {
+                vals.push(val);
+            }
+            (vals, empty || val.is_empty())
+        });
+        if vals.is_empty() {
+            return Self::Text
+        }
+        if vals.iter().all(|v| v.parse::<f64>().is_ok()) {
+            Self::Number
+        } else if !empty && vals.iter().all(|v| is_boolean(v)) {
+            Self::Boolean
+        } else if vals.iter().all(|v| is_date(v)) {
+            Self::Date
+        } else {
+            let choices = choices(vals.iter().copied()).len();
+            if (2..=MAX_CHOICES).contains(&choices) && choices * 2 <= vals.len()
+                && vals.iter().all(|v| !v.contains(&['\r', '\n'][..])) {
+                Self::Choice
+            } else {
+                Self::Text
+            }
+        }
+    }
+}
+
+/// Distinct values of a column in sorted order.
+pub(crate) fn choices<'a>(vals: impl Iterator<Item=&'a str>) -> Vec<&'a str> {
+    vals.collect::<BTreeSet<_>>().into_iter().collect()
+}
+
+/// Values the grid check box toggles between.
+fn is_boolean(val: &str) -> bool {
+    matches!(val, "true" | "false" | "on" | "off")
+}
+
+/// `YYYY-MM-DD`, the value format of a date input.
+fn is_date(val: &str) -> bool {
+    let bytes = val.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return false
+    }
+    let number = |range: std::ops::Range<usize>| val.get(range)
+        .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|digits| digits.parse::<u32>().ok());
+    matches!((number(0..4), number(5..7), number(8..10)), (Some(_), Some(1..=12), Some(1..=31)))
+}
+
+/// View settings of a column. They are not part of the csv.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Column {
+    pub(crate) kind: Option<ColumnType>,
+    /// Width set by resizing the column. `None` fits the values and the caption.
+    pub(crate) width: Option<usize>,
+    pub(crate) hidden: bool,
+}
+
+/// View settings of all columns, indexed by csv column.
+#[derive(Debug, Default)]
+pub(crate) struct Columns {
+    columns: Vec<Column>,
+    /// Display order of the csv columns. Columns missing from it follow in file order.
+    order: Vec<usize>,
+    /// Whether saving writes the columns in display order.
+    apply_order: bool,
+    /// Number of leading visible columns kept in view when scrolling sideways.
+    frozen: usize,
+}
+
+impl Columns {
+    /// Layout stored for a file name, if any.
+    pub(crate) fn load(name: &str) -> Self {
+        let layout = LocalStorage::get::<(Vec<usize>, Vec<usize>, bool, usize)>(layout_key(name));
+        let (order, hidden, apply_order, frozen) = if let Ok(layout) = layout {
+            layout
+        } else {
+            return Self::default()
+        };
+        let mut columns = Self { columns: vec![], order, apply_order, frozen };
+        for col in hidden {
+            columns.get_mut(col).hidden = true;
+        }
+        columns
+    }
+
+    /// Stores the order, hidden and frozen columns and the save option for a file name.
+    pub(crate) fn save(&self, name: &str) -> Result<(), JsValue> {
+        let hidden = (0..self.columns.len()).filter(|&col| self.get(col).hidden).collect::<Vec<_>>();
+        LocalStorage::set(layout_key(name), (&self.order, hidden, self.apply_order, self.frozen))
+            .map_err(|e| e.to_string().into())
+    }
+
+    pub(crate) fn get(&self, col: usize) -> Column {
+        self.columns.get(col).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn get_mut(&mut self, col: usize) -> &mut Column {
+        if self.columns.len() <= col {
+            self.columns.resize_with(col + 1, Default::default);
+        }
+        &mut self.columns[col]
+    }
+
+    pub(crate) fn insert(&mut self, col: usize) {
+        if col < self.columns.len() {
+            self.columns.insert(col, Column::default());
+        }
+        if self.order.is_empty() {
+            return
+        }
+        let position = col.checked_sub(1)
+            .and_then(|prev| self.order.iter().position(|&c| c == prev))
+            .map(|position| position + 1)
+            .unwrap_or(0);
+        for c in &mut self.order {
+            if *c >= col {
+                *c += 1;
+            }
+        }
+        self.order.insert(position, col);
+    }
+
+    pub(crate) fn remove(&mut self, col: usize) {
+        if col < self.columns.len() {
+            self.columns.remove(col);
+        }
+        self.order.retain(|&c| c != col);
+        for c in &mut self.order {
+            if *c > col {
+                *c -= 1;
+            }
+        }
+    }
+
+    /// Makes every column fit its values again.
+    pub(crate) fn fit_all(&mut self) {
+        for column in &mut self.columns {
+            column.width = None;
+        }
+    }
+
+    pub(crate) fn show_all(&mut self) {
+        for column in &mut self.columns {
+            column.hidden = false;
+        }
+    }
+
+    /// All csv columns in display order, hidden ones included.
+    pub(crate) fn order(&self, max_cols: usize) -> Vec<usize> {
+        let mut order = self.order.iter().copied().filter(|&col| col < max_cols).collect::<Vec<_>>();
+        order.extend((0..max_cols).filter(|col| !self.order.contains(col)));
+        order
+    }
+
+    /// Csv columns in display order.
+    pub(crate) fn visible(&self, max_cols: usize) -> Vec<usize> {
+        self.order(max_cols).into_iter().filter(|&col| !self.get(col).hidden).collect()
+    }
+
+    /// Moves csv column `col` to where csv column `target` is displayed.
+    pub(crate) fn move_col(&mut self, col: usize, target: usize, max_cols: usize) {
+        let mut order = self.order(max_cols);
+        let (from, to) = match (order.iter().position(|&c| c == col), order.iter().position(|&c| c == target)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return,
+        };
+        order.remove(from);
+        order.insert(to, col);
+        self.order = order;
+    }
+
+    /// Follows the csv columns after they were moved into `order`, which becomes the file order.
+    pub(crate) fn reorder(&mut self, order: &[usize]) {
+        self.columns = order.iter().map(|&col| self.get(col)).collect();
+        self.order.clear();
+    }
+
+    pub(crate) fn reset_order(&mut self) {
+        self.order.clear();
+    }
+
+    pub(crate) fn frozen(&self) -> usize {
+        self.frozen
+    }
+
+    pub(crate) fn set_frozen(&mut self, count: usize) {
+        self.frozen = count;
+    }
+
+    pub(crate) fn apply_order(&self) -> bool {
+        self.apply_order
+    }
+
+    pub(crate) fn set_apply_order(&mut self, apply: bool) {
+        self.apply_order = apply;
+    }
+
+    /// Column order to write on save, when it differs from the file order.
+    pub(crate) fn save_order(&self, max_cols: usize) -> Option<Vec<usize>> {
+        let order = self.order(max_cols);
+        Some(order).filter(|order| self.apply_order && order.iter().enumerate().any(|(i, &col)| i != col))
+    }
+}
+
+fn layout_key(name: &str) -> String {
+    format!("layout:{}", name)
+}
@@ -0,0 +1,68 @@
+use csvparser::{Csv, quote};
+
+use crate::grid::Selection;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Format {
+    Tsv,
+    Csv,
+    Markdown,
+}
+
+impl Format {
+    pub(crate) fn from_action(action: &str) -> Option<Self> {
+        match action {
+            "copy_tsv" => Some(Self::Tsv),
+            "copy_csv" => Some(Self::Csv),
+            "copy_markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+fn caption(csv: &Csv, col: usize) -> String {
+    csv.header(col).map(ToString::to_string).unwrap_or_else(|| col.to_string())
+}
+
+fn markdown_escape(val: &str) -> String {
+    val.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+fn delimited(csv: &Csv, selection: &Selection, delimiter: char, eol: &str) -> String {
+    let mut lines = vec![];
+    if selection.header && csv.has_header() {
+        let line = selection.cols.iter()
+            .map(|&col| quote(csv.header(col).unwrap_or(""), delimiter).into_owned())
+            .collect::<Vec<_>>();
+        lines.push(line.join(&delimiter.to_string()));
+    }
+    for &row in &selection.rows {
+        let line = selection.cols.iter()
+            .map(|&col| quote(csv.val(row, col).unwrap_or(""), delimiter).into_owned())
+            .collect::<Vec<_>>();
+        lines.push(line.join(&delimiter.to_string()));
+    }
+    lines.join(eol)
+}
+
+fn markdown(csv: &Csv, selection: &Selection) -> String {
+    let mut lines = vec![];
+    let header = selection.cols.iter().map(|&col| markdown_escape(&caption(csv, col))).collect::<Vec<_>>();
+    lines.push(format!("| {} |", header.join(" | ")));
+    lines.push(format!("|{}|", selection.cols.iter().map(|_| " --- ").collect::<Vec<_>>().join("|")));
+    for &row in &selection.rows {
+        let line = selection.cols.iter()
+            .map(|&col| markdown_escape(csv.val(row, col).unwrap_or("")))
+            .collect::<Vec<_>>();
+        lines.push(format!("| {} |", line.join(" | ")));
+    }
+    lines.join("\n")
+}
+
+pub(crate) fn serialize(csv: &Csv, selection: &Selection, format: Format) -> String {
+    match format {
+        Format::Tsv => delimited(csv, selection, '\t', "\n"),
+        Format::Csv => delimited(csv, selection, ',', "\r\n"),
+        Format::Markdown => markdown(csv, selection),
+    }
+}
@@ -0,0 +1,139 @@
+use web_sys::Element;
+use encoding::EncodingRef;
+
+use crate::grid::Grid;
+use crate::session::Snapshot;
+use crate::sys::file_system_access::FileSystemFileHandle;
+
+pub(crate) struct Document {
+    element: Element,
+    grid: Grid,
+    coder: Option<EncodingRef>,
+    handle: Option<FileSystemFileHandle>,
+    saved_revision: Option<u64>,
+    dirty: bool,
+    can_undo: bool,
+    can_redo: bool,
+    has_header: bool,
+}
+
+impl Document {
+    pub(crate) fn new(
+        element: Element,
+        grid: Grid,
+        coder: Option<EncodingRef>,
+        handle: Option<FileSystemFileHandle>) -> Self {
+
+        Self {
+            element,
+            grid,
+            coder,
+            handle,
+            saved_revision: Some(0),
+            dirty: false,
+            can_undo: false,
+            can_redo: false,
+            has_header: false,
+        }
+    }
+
+    /// Name of the encoding the file was decoded with.
+    pub(crate) fn encoding(&self) -> &'static str {
+        self.coder.and_then(|coder| coder.whatwg_name()).unwrap_or("utf-8")
+    }
+
+    pub(crate) fn element(&self) -> &Element {
+        &self.element
+    }
+
+    pub(crate) fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        self.grid.name()
+    }
+
+    pub(crate) fn set_name(&mut self, name: String) {
+        self.grid.set_name(name);
+    }
+
+    pub(crate) fn read_only(&self) -> bool {
+        self.grid.read_only()
+    }
+
+    pub(crate) fn set_read_only(&mut self, val: bool) {
+        self.grid.set_read_only(val);
+    }
+
+    pub(crate) fn handle(&self) -> Option<&FileSystemFileHandle> {
+        self.handle.as_ref()
+    }
+
+    pub(crate) fn set_handle(&mut self, handle: FileSystemFileHandle) {
+        self.grid.set_name(handle.name());
+        self.handle = Some(handle);
+    }
+
+    pub(crate) async fn snapshot(&self) -> Snapshot {
+        let csv = self.grid.csv();
+        let csv = csv.lock().await;
+        Snapshot {
+            name: self.name().to_string(),
+            text: format!("{}", &*csv),
+            encoding: self.coder.and_then(|coder| coder.whatwg_name()).map(ToString::to_string),
+            header: csv.has_header(),
+            delimiter: csv.delimiter(),
+        }
+    }
+
+    pub(crate) async fn to_bytes(&self) -> Vec<u8> {
+        let csv_content = {
+            let csv = self.grid.csv();
+            let csv = csv.lock().await;
+            format!("{}", &*csv)
+        };
+        let content = if let Some(coder) = self.coder {
+            coder.encode(&csv_content, encoding::EncoderTrap::Replace).ok()
+        } else {
+            None
+        };
+        content.unwrap_or_else(|| csv_content.into_bytes())
+    }
+
+    pub(crate) fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        self.can_undo
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        self.can_redo
+    }
+
+    pub(crate) fn has_header(&self) -> bool {
+        self.has_header
+    }
+
+    pub(crate) async fn update_dirty(&mut self) -> bool {
+        let csv = self.grid.csv();
+        let csv = csv.lock().await;
+        self.dirty = Some(csv.revision()) != self.saved_revision;
+        self.can_undo = csv.can_undo();
+        self.can_redo = csv.can_redo();
+        self.has_header = csv.has_header();
+        self.dirty
+    }
+
+    pub(crate) async fn mark_saved(&mut self) {
+        self.saved_revision = Some(self.grid.csv().lock().await.revision());
+        self.dirty = false;
+    }
+
+    pub(crate) fn mark_unsaved(&mut self) {
+        self.saved_revision = None;
+        self.dirty = true;
+    }
+}
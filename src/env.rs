@@ -1,10 +1,13 @@
 use wasm_bindgen::JsValue;
 use wasm_bindgen::JsCast;
+use js_sys::Reflect;
 use web_sys::{
+    Window,
     Location,
     Element,
     HtmlInputElement,
     HtmlButtonElement,
+    HtmlSelectElement,
 };
 use crate::sys::material::{
     MDCTopAppBar,
@@ -16,17 +19,54 @@ use crate::sys::material::{
 
 #[derive(Debug)]
 pub(crate) struct Env {
+    window: Window,
     location: Location,
     root: Element,
+    documents: Element,
+    tabs: Element,
     input_file: HtmlInputElement,
     menu: Element,
+    column_menu: Element,
     header: Element,
+    app_open_url: Element,
+    app_paste_new: Element,
+    app_read_only: HtmlButtonElement,
+    app_undo: HtmlButtonElement,
+    app_redo: HtmlButtonElement,
     app_save: HtmlButtonElement,
+    app_save_as: HtmlButtonElement,
     app_use_header: HtmlInputElement,
+    app_modified: Element,
+    quick_filter: HtmlInputElement,
+    quick_filter_count: Element,
+    app_find_toggle: HtmlButtonElement,
+    app_keymap_toggle: HtmlButtonElement,
+    keymap: Element,
+    keymap_table: Element,
+    palette: Element,
+    palette_input: HtmlInputElement,
+    palette_list: Element,
+    find: Element,
+    find_text: HtmlInputElement,
+    replace_text: HtmlInputElement,
+    find_mode: HtmlSelectElement,
+    find_scope: HtmlSelectElement,
+    find_status: Element,
+    goto: Element,
+    goto_text: HtmlInputElement,
+    filter: Element,
+    filter_column: Element,
+    filter_kind: HtmlSelectElement,
+    filter_value: HtmlInputElement,
+    filter_max: HtmlInputElement,
+    recent: Element,
+    status: Element,
     error: Element,
+    file_system_access: bool,
 
     mdc_drawer: MDCDrawer,
     mdc_menu: MDCMenu,
+    mdc_column_menu: MDCMenu,
 }
 
 impl Env {
@@ -35,39 +75,133 @@ impl Env {
         let location = window.location();
         let document = window.document().ok_or("No document found.")?;
         let root = document.query_selector("main")?.ok_or("No root found.")?;
+        let documents = document.query_selector("#documents")?.ok_or("Element not found")?;
+        let tabs = document.query_selector(".app-tabs")?.ok_or("Element not found")?;
         let header = document.query_selector("header")?.ok_or("No header found")?;
         let aside = document.query_selector("aside")?.ok_or("No aside found")?;
 
         let input_file = document.query_selector("input[type='file']")?.ok_or("Element not found")?
             .dyn_into::<HtmlInputElement>()?;
+        let app_open_url = document.query_selector(".app-open-url")?.ok_or("Element not found")?;
+        let app_paste_new = document.query_selector(".app-paste-new")?.ok_or("Element not found")?;
+        let app_read_only = document.query_selector(".app-read-only")?.ok_or("Element not found")?
+            .dyn_into::<HtmlButtonElement>()?;
+        let app_undo = document.query_selector(".app-undo")?.ok_or("Element not found")?
+            .dyn_into::<HtmlButtonElement>()?;
+        let app_redo = document.query_selector(".app-redo")?.ok_or("Element not found")?
+            .dyn_into::<HtmlButtonElement>()?;
         let app_save = document.query_selector(".app-save")?.ok_or("Element not found")?
             .dyn_into::<HtmlButtonElement>()?;
+        let app_save_as = document.query_selector(".app-save-as")?.ok_or("Element not found")?
+            .dyn_into::<HtmlButtonElement>()?;
         let app_use_header = document.query_selector(".app-use-header")?.ok_or("Element not found")?
             .dyn_into::<HtmlInputElement>()?;
+        let app_modified = document.query_selector(".app-modified")?.ok_or("Element not found")?;
+        let quick_filter = document.query_selector(".app-quick-filter")?.ok_or("Element not found")?
+            .dyn_into::<HtmlInputElement>()?;
+        let quick_filter_count = document.query_selector(".app-quick-filter-count")?.ok_or("Element not found")?;
+        let app_keymap_toggle = document.query_selector(".app-keymap-toggle")?.ok_or("Element not found")?
+            .dyn_into::<HtmlButtonElement>()?;
+        let keymap = document.query_selector(".app-keymap")?.ok_or("Element not found")?;
+        let keymap_table = document.query_selector(".app-keymap__table tbody")?.ok_or("Element not found")?;
+        let palette = document.query_selector(".app-palette")?.ok_or("Element not found")?;
+        let palette_input = document.query_selector(".app-palette__input")?.ok_or("Element not found")?
+            .dyn_into::<HtmlInputElement>()?;
+        let palette_list = document.query_selector(".app-palette__list")?.ok_or("Element not found")?;
+        let status = document.query_selector(".app-status")?.ok_or("Element not found")?;
+        let goto = document.query_selector(".app-goto")?.ok_or("Element not found")?;
+        let goto_text = document.query_selector(".app-goto-text")?.ok_or("Element not found")?
+            .dyn_into::<HtmlInputElement>()?;
+        let app_find_toggle = document.query_selector(".app-find-toggle")?.ok_or("Element not found")?
+            .dyn_into::<HtmlButtonElement>()?;
+        let find = document.query_selector(".app-find")?.ok_or("Element not found")?;
+        let find_text = document.query_selector(".app-find-text")?.ok_or("Element not found")?
+            .dyn_into::<HtmlInputElement>()?;
+        let replace_text = document.query_selector(".app-replace-text")?.ok_or("Element not found")?
+            .dyn_into::<HtmlInputElement>()?;
+        let find_mode = document.query_selector(".app-find-mode")?.ok_or("Element not found")?
+            .dyn_into::<HtmlSelectElement>()?;
+        let find_scope = document.query_selector(".app-find-scope")?.ok_or("Element not found")?
+            .dyn_into::<HtmlSelectElement>()?;
+        let find_status = document.query_selector(".app-find-status")?.ok_or("Element not found")?;
+        let filter = document.query_selector(".app-filter")?.ok_or("Element not found")?;
+        let filter_column = document.query_selector(".app-filter-column")?.ok_or("Element not found")?;
+        let filter_kind = document.query_selector(".app-filter-kind")?.ok_or("Element not found")?
+            .dyn_into::<HtmlSelectElement>()?;
+        let filter_value = document.query_selector(".app-filter-value")?.ok_or("Element not found")?
+            .dyn_into::<HtmlInputElement>()?;
+        let filter_max = document.query_selector(".app-filter-max")?.ok_or("Element not found")?
+            .dyn_into::<HtmlInputElement>()?;
+        let recent = document.query_selector(".app-recent")?.ok_or("Element not found")?;
         let form_field_use_header = document.query_selector(".mdc-form-field")?.ok_or("Element not found")?;
-        let menu = document.query_selector(".mdc-menu")?.ok_or("Element not found")?;
+        let menu = document.query_selector(".app-row-menu")?.ok_or("Element not found")?;
+        let column_menu = document.query_selector(".app-column-menu")?.ok_or("Element not found")?;
         let error = document.query_selector("#error")?.ok_or("Element not found")?;
 
+        let file_system_access = Reflect::has(&window, &"showOpenFilePicker".into())?;
+
         MDCTopAppBar::new(&header)?.set_scroll_target(&header)?;
         let mdc_drawer = MDCDrawer::attachTo(&aside)?;
         MDCFormField::new(&form_field_use_header)?.set_input(
             MDCCheckbox::new(&app_use_header.parent_element().ok_or("Element not found")?)?.as_ref());
         let mdc_menu = MDCMenu::new(&menu)?;
+        let mdc_column_menu = MDCMenu::new(&column_menu)?;
 
         Ok(Self {
+            window,
             location,
             root,
+            documents,
+            tabs,
             input_file,
             menu,
+            column_menu,
             error,
+            file_system_access,
             header,
+            app_open_url,
+            app_paste_new,
+            app_read_only,
+            app_undo,
+            app_redo,
             app_save,
+            app_save_as,
             app_use_header,
+            app_modified,
+            quick_filter,
+            quick_filter_count,
+            app_find_toggle,
+            app_keymap_toggle,
+            keymap,
+            keymap_table,
+            palette,
+            palette_input,
+            palette_list,
+            find,
+            find_text,
+            replace_text,
+            find_mode,
+            find_scope,
+            find_status,
+            goto,
+            goto_text,
+            filter,
+            filter_column,
+            filter_kind,
+            filter_value,
+            filter_max,
+            recent,
+            status,
             mdc_drawer,
             mdc_menu,
+            mdc_column_menu,
         })
     }
 
+    pub(crate) fn window(&self) -> &Window {
+        &self.window
+    }
+
     pub(crate) fn location(&self) -> &Location {
         &self.location
     }
@@ -76,6 +210,14 @@ impl Env {
         &self.root
     }
 
+    pub(crate) fn documents(&self) -> &Element {
+        &self.documents
+    }
+
+    pub(crate) fn tabs(&self) -> &Element {
+        &self.tabs
+    }
+
     pub(crate) fn header(&self) -> &Element {
         &self.header
     }
@@ -84,14 +226,138 @@ impl Env {
         &self.input_file
     }
 
+    pub(crate) fn app_open_url(&self) -> &Element {
+        &self.app_open_url
+    }
+
+    pub(crate) fn app_paste_new(&self) -> &Element {
+        &self.app_paste_new
+    }
+
+    pub(crate) fn app_read_only(&self) -> &HtmlButtonElement {
+        &self.app_read_only
+    }
+
+    pub(crate) fn app_undo(&self) -> &HtmlButtonElement {
+        &self.app_undo
+    }
+
+    pub(crate) fn app_redo(&self) -> &HtmlButtonElement {
+        &self.app_redo
+    }
+
     pub(crate) fn app_save(&self) -> &HtmlButtonElement {
         &self.app_save
     }
 
+    pub(crate) fn app_save_as(&self) -> &HtmlButtonElement {
+        &self.app_save_as
+    }
+
     pub(crate) fn app_use_header(&self) -> &HtmlInputElement {
         &self.app_use_header
     }
 
+    pub(crate) fn app_modified(&self) -> &Element {
+        &self.app_modified
+    }
+
+    pub(crate) fn quick_filter(&self) -> &HtmlInputElement {
+        &self.quick_filter
+    }
+
+    pub(crate) fn quick_filter_count(&self) -> &Element {
+        &self.quick_filter_count
+    }
+
+    pub(crate) fn app_find_toggle(&self) -> &HtmlButtonElement {
+        &self.app_find_toggle
+    }
+
+    pub(crate) fn app_keymap_toggle(&self) -> &HtmlButtonElement {
+        &self.app_keymap_toggle
+    }
+
+    pub(crate) fn keymap(&self) -> &Element {
+        &self.keymap
+    }
+
+    pub(crate) fn keymap_table(&self) -> &Element {
+        &self.keymap_table
+    }
+
+    pub(crate) fn palette(&self) -> &Element {
+        &self.palette
+    }
+
+    pub(crate) fn palette_input(&self) -> &HtmlInputElement {
+        &self.palette_input
+    }
+
+    pub(crate) fn palette_list(&self) -> &Element {
+        &self.palette_list
+    }
+
+    pub(crate) fn find(&self) -> &Element {
+        &self.find
+    }
+
+    pub(crate) fn find_text(&self) -> &HtmlInputElement {
+        &self.find_text
+    }
+
+    pub(crate) fn replace_text(&self) -> &HtmlInputElement {
+        &self.replace_text
+    }
+
+    pub(crate) fn find_mode(&self) -> &HtmlSelectElement {
+        &self.find_mode
+    }
+
+    pub(crate) fn find_scope(&self) -> &HtmlSelectElement {
+        &self.find_scope
+    }
+
+    pub(crate) fn find_status(&self) -> &Element {
+        &self.find_status
+    }
+
+    pub(crate) fn goto(&self) -> &Element {
+        &self.goto
+    }
+
+    pub(crate) fn goto_text(&self) -> &HtmlInputElement {
+        &self.goto_text
+    }
+
+    pub(crate) fn filter(&self) -> &Element {
+        &self.filter
+    }
+
+    pub(crate) fn filter_column(&self) -> &Element {
+        &self.filter_column
+    }
+
+    pub(crate) fn filter_kind(&self) -> &HtmlSelectElement {
+        &self.filter_kind
+    }
+
+    pub(crate) fn filter_value(&self) -> &HtmlInputElement {
+        &self.filter_value
+    }
+
+    pub(crate) fn filter_max(&self) -> &HtmlInputElement {
+        &self.filter_max
+    }
+
+    pub(crate) fn recent(&self) -> &Element {
+        &self.recent
+    }
+
+    pub(crate) fn status(&self) -> &Element {
+        &self.status
+    }
+
     pub(crate) fn error(&self) -> &Element {
         &self.error
     }
@@ -100,6 +366,14 @@ impl Env {
         &self.menu
     }
 
+    pub(crate) fn column_menu(&self) -> &Element {
+        &self.column_menu
+    }
+
+    pub(crate) fn file_system_access(&self) -> bool {
+        self.file_system_access
+    }
+
     pub(crate) fn mdc_drawer(&self) -> &MDCDrawer {
         &self.mdc_drawer
     }
@@ -108,4 +382,8 @@ impl Env {
         &self.mdc_menu
     }
 
+    pub(crate) fn mdc_column_menu(&self) -> &MDCMenu {
+        &self.mdc_column_menu
+    }
+
 }
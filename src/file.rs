@@ -0,0 +1,107 @@
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use js_sys::{Array, Reflect, Uint8Array};
+use web_sys::{Location, Url, File, FilePropertyBag};
+
+use crate::sys::file_system_access::{
+    self,
+    FileSystemFileHandle,
+    FileSystemWritableFileStream,
+};
+
+fn types() -> Array {
+    let accept = js! {
+        "text/csv" => [".csv"].iter().map(|e| JsValue::from(*e)).collect::<Array>()
+    };
+    Array::of1(js! {
+        "description" => "CSV",
+        "accept" => accept
+    }.as_ref())
+}
+
+fn is_abort(err: &JsValue) -> bool {
+    #[allow(unused_unsafe)]
+    let name = unsafe {
+        Reflect::get(err, &"name".into()).ok()
+    };
+    name.and_then(|name| name.as_string()).as_deref() == Some("AbortError")
+}
+
+/// Whether two handles point to the same file. Files of the same name may live in different folders.
+pub(crate) async fn same_entry(a: &FileSystemFileHandle, b: &FileSystemFileHandle) -> bool {
+    JsFuture::from(a.is_same_entry(b)).await.ok().and_then(|same| same.as_bool()).unwrap_or(false)
+}
+
+pub(crate) async fn fetch(url: &str) -> Result<Vec<u8>, JsValue> {
+    use gloo::net::http::Request;
+
+    let response = Request::get(url).send().await
+        .map_err(|e| format!("failed to fetch {} {}", url, e))?;
+    if !response.ok() {
+        return Err(format!("failed to fetch {} {} {}", url, response.status(), response.status_text()).into())
+    }
+    let bytes = response.binary().await
+        .map_err(|e| format!("failed to fetch {} {}", url, e))?;
+    Ok(bytes)
+}
+
+pub(crate) async fn pick_open_files() -> Result<Vec<(gloo::file::File, FileSystemFileHandle)>, JsValue> {
+    let options = js! {
+        "types" => types(),
+        "multiple" => true
+    };
+    let handles = match JsFuture::from(file_system_access::show_open_file_picker(&options)?).await {
+        Ok(handles) => handles.dyn_into::<Array>()?,
+        Err(err) if is_abort(&err) => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+
+    let mut files = vec![];
+    for handle in handles.iter() {
+        let handle = handle.unchecked_into::<FileSystemFileHandle>();
+        let file = JsFuture::from(handle.get_file()).await?.dyn_into::<File>()?;
+        files.push((gloo::file::File::from(file), handle));
+    }
+    Ok(files)
+}
+
+pub(crate) async fn open_handle(handle: &FileSystemFileHandle) -> Result<gloo::file::File, JsValue> {
+    let permission = JsFuture::from(handle.request_permission(&js! { "mode" => "readwrite" })).await?;
+    if permission.as_string().as_deref() != Some("granted") {
+        return Err(format!("permission denied {}", handle.name()).into())
+    }
+    let file = JsFuture::from(handle.get_file()).await?.dyn_into::<File>()?;
+    Ok(gloo::file::File::from(file))
+}
+
+pub(crate) async fn pick_save_file(name: &str) -> Result<Option<FileSystemFileHandle>, JsValue> {
+    let options = js! {
+        "types" => types(),
+        "suggestedName" => name
+    };
+    match JsFuture::from(file_system_access::show_save_file_picker(&options)?).await {
+        Ok(handle) => Ok(Some(handle.unchecked_into())),
+        Err(err) if is_abort(&err) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+pub(crate) async fn write(handle: &FileSystemFileHandle, content: &[u8]) -> Result<(), JsValue> {
+    let writable = JsFuture::from(handle.create_writable()).await?
+        .unchecked_into::<FileSystemWritableFileStream>();
+    JsFuture::from(writable.write(&Uint8Array::from(content))).await?;
+    JsFuture::from(writable.close()).await?;
+    Ok(())
+}
+
+pub(crate) fn download(location: &Location, name: &str, content: &[u8]) -> Result<(), JsValue> {
+    let parts = Array::of1(Uint8Array::from(content).buffer().as_ref());
+    let blob = File::new_with_buffer_source_sequence_and_options(
+        &parts,
+        name,
+        FilePropertyBag::new().type_("text/csv"))?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+    location.assign(&url)?;
+    Url::revoke_object_url(&url)?;
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use wasm_bindgen::JsValue;
+use csvparser::{Csv, Finder, Mode};
+
+#[derive(Debug, Clone)]
+pub(crate) enum Condition {
+    Equals(String),
+    Contains(String),
+    Regex(Finder),
+    Range(Option<f64>, Option<f64>),
+    Empty,
+    NonEmpty,
+}
+
+impl Condition {
+    pub(crate) fn new(kind: &str, value: &str, max: &str) -> Result<Self, JsValue> {
+        let number = |v: &str| match v.trim() {
+            "" => Ok(None),
+            v => v.parse::<f64>().map(Some).map_err(|_| JsValue::from(format!("{} is not a number", v))),
+        };
+        match kind {
+            "equals" => Ok(Self::Equals(value.to_string())),
+            "contains" => Ok(Self::Contains(value.to_string())),
+            "regex" => Finder::new(value, Mode::Regex).map(Self::Regex).map_err(|e| e.to_string().into()),
+            "range" => Ok(Self::Range(number(value)?, number(max)?)),
+            "empty" => Ok(Self::Empty),
+            "non_empty" => Ok(Self::NonEmpty),
+            _ => Err(format!("unknown filter {}", kind).into()),
+        }
+    }
+
+    fn matches(&self, val: &str) -> bool {
+        match self {
+            Self::Equals(v) => val == v,
+            Self::Contains(v) => val.contains(v.as_str()),
+            Self::Regex(finder) => finder.is_match(val),
+            Self::Range(min, max) => match val.trim().parse::<f64>() {
+                Ok(n) => min.map(|min| n >= min).unwrap_or(true) && max.map(|max| n <= max).unwrap_or(true),
+                Err(_) => false,
+            },
+            Self::Empty => val.is_empty(),
+            Self::NonEmpty => !val.is_empty(),
+        }
+    }
+}
+
+/// Conditions on csv columns. A row is shown when it matches all of them
+/// and, if set, contains the quick filter text in any cell.
+#[derive(Debug, Default)]
+pub(crate) struct Filters {
+    conditions: Vec<(usize, Condition)>,
+    quick: String,
+}
+
+impl Filters {
+    pub(crate) fn quick(&self) -> &str {
+        &self.quick
+    }
+
+    pub(crate) fn set_quick(&mut self, text: &str) {
+        self.quick = text.to_string();
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.conditions.is_empty() && self.quick.is_empty()
+    }
+
+    pub(crate) fn is_filtered(&self, col: usize) -> bool {
+        self.conditions.iter().any(|(c, _)| *c == col)
+    }
+
+    pub(crate) fn set(&mut self, col: usize, condition: Condition) {
+        self.remove(col);
+        self.conditions.push((col, condition));
+    }
+
+    pub(crate) fn remove(&mut self, col: usize) {
+        self.conditions.retain(|(c, _)| *c != col);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.conditions.clear();
+    }
+
+    /// Shifts the conditions after a column was inserted at `col`.
+    pub(crate) fn insert_col(&mut self, col: usize) {
+        for (c, _) in &mut self.conditions {
+            if *c >= col {
+                *c += 1;
+            }
+        }
+    }
+
+    /// Drops the condition of a removed column and shifts the others.
+    pub(crate) fn remove_col(&mut self, col: usize) {
+        self.remove(col);
+        for (c, _) in &mut self.conditions {
+            if *c > col {
+                *c -= 1;
+            }
+        }
+    }
+
+    /// Follows the csv columns after they were moved into `order`.
+    pub(crate) fn reorder_cols(&mut self, order: &[usize]) {
+        for (c, _) in &mut self.conditions {
+            if let Some(position) = order.iter().position(|col| col == c) {
+                *c = position;
+            }
+        }
+    }
+
+    /// Csv rows to show, or `None` when nothing is filtered.
+    pub(crate) fn rows(&self, csv: &Csv) -> Option<Vec<usize>> {
+        if self.is_empty() {
+            return None
+        }
+        let quick = self.quick.to_lowercase();
+        Some((0..csv.rows()).filter(|&row| {
+            self.conditions.iter().all(|(col, condition)| condition.matches(csv.val(row, *col).unwrap_or("")))
+                && (quick.is_empty() || csv.vals(row).any(|val| val.to_lowercase().contains(&quick)))
+        }).collect())
+    }
+}
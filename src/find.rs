@@ -0,0 +1,53 @@
+use wasm_bindgen::JsValue;
+use csvparser::{Csv, Finder, Mode};
+
+use crate::grid::{Grid, Selection};
+
+pub(crate) fn finder(pattern: &str, mode: &str) -> Result<Finder, JsValue> {
+    let mode = match mode {
+        "case_insensitive" => Mode::CaseInsensitive,
+        "whole_cell" => Mode::WholeCell,
+        "regex" => Mode::Regex,
+        _ => Mode::Literal,
+    };
+    Finder::new(pattern, mode).map_err(|e| e.to_string().into())
+}
+
+/// Rows and columns to search. `selection` is the selection captured when the search started.
+pub(crate) fn scope(
+    grid: &Grid,
+    csv: &Csv,
+    scope: &str,
+    selection: Option<&Selection>) -> Result<(Vec<usize>, Vec<usize>), JsValue> {
+
+    match (scope, selection) {
+        ("selection", Some(selection)) => Ok((selection.rows.clone(), selection.cols.clone())),
+        ("column", _) => {
+            let col = grid.cursor(csv)?.map(|(_, col)| col).ok_or("Select a cell in the column to search.")?;
+            Ok((grid.visible_rows(csv), vec![col]))
+        }
+        _ => Ok((grid.visible_rows(csv), grid.visible_cols(csv))),
+    }
+}
+
+/// Match after (or before) the cursor, wrapping around.
+pub(crate) fn next(
+    matches: &[(usize, usize)],
+    cursor: Option<(usize, usize)>,
+    visible: &[usize],
+    forward: bool) -> Option<(usize, usize)> {
+
+    let position = |(row, col): (usize, usize)| (row, visible.iter().position(|&c| c == col));
+    let cursor = cursor.map(position);
+    if forward {
+        matches.iter()
+            .find(|&&m| cursor.map(|cursor| position(m) > cursor).unwrap_or(true))
+            .or_else(|| matches.first())
+            .copied()
+    } else {
+        matches.iter().rev()
+            .find(|&&m| cursor.map(|cursor| position(m) < cursor).unwrap_or(true))
+            .or_else(|| matches.last())
+            .copied()
+    }
+}
@@ -0,0 +1,41 @@
+use wasm_bindgen::JsValue;
+use csvparser::Csv;
+
+/// Parses a 1-based `row`, `row:column` or `header row` (or `header:row`) address into a csv cell.
+/// The column may be a number or a header name, and is `None` when only a row is given.
+pub(crate) fn parse(csv: &Csv, address: &str) -> Result<(usize, Option<usize>), JsValue> {
+    let address = address.trim();
+    let (row, col) = if let Some((left, right)) = address.split_once(':') {
+        let (left, right) = (left.trim(), right.trim());
+        if number(left).is_none() && number(right).is_some() {
+            (right, Some(left))
+        } else {
+            (left, Some(right))
+        }
+    } else if let Some((col, row)) = address.rsplit_once(char::is_whitespace) {
+        (row.trim(), Some(col.trim()))
+    } else {
+        (address, None)
+    };
+    let row = number(row).filter(|&r| r < csv.rows())
+        .ok_or_else(|| format!("There is no row {}.", row))?;
+    let col = match col {
+        Some(col) => Some(column(csv, col).ok_or_else(|| format!("There is no column {}.", col))?),
+        None => None,
+    };
+    Ok((row, col))
+}
+
+/// Zero-based index of a 1-based number. Digit group separators are ignored.
+fn number(text: &str) -> Option<usize> {
+    let digits = text.chars().filter(|&c| c != ',' && c != '_').collect::<String>();
+    digits.parse::<usize>().ok()?.checked_sub(1)
+}
+
+/// Column by header name, case-insensitive header name or 1-based number.
+fn column(csv: &Csv, name: &str) -> Option<usize> {
+    let lower = name.to_lowercase();
+    (0..csv.max_cols()).find(|&col| csv.header(col) == Some(name))
+        .or_else(|| (0..csv.max_cols()).find(|&col| csv.header(col).map(str::to_lowercase).as_deref() == Some(&lower)))
+        .or_else(|| number(name).filter(|&col| col < csv.max_cols()))
+}
@@ -1,24 +1,248 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 use std::sync::Arc;
 
-use wasm_bindgen::JsValue;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen_futures::{future_to_promise, spawn_local};
-use js_sys::{Object, Array, Promise, Reflect};
-use web_sys::Element;
+use js_sys::{Object, Array, Function, Promise, Reflect};
+use web_sys::{ClipboardEvent, Element, Event, EventInit, HtmlInputElement, HtmlTextAreaElement, KeyboardEvent};
+use gloo::events::{EventListener, EventListenerOptions, EventListenerPhase};
 use futures::lock::Mutex;
 use unicode_width::UnicodeWidthStr;
-use csvparser::Csv;
+use csvparser::{ColumnChange, Csv};
 
 use crate::sys::cheetah_grid;
+use crate::column::{choices, ColumnType, Columns};
+use crate::filter::Filters;
 
-macro_rules! js {
-    ( $( $key:expr => $val:expr ),* ) => {
-        {
-            let entries = [
-                $( [JsValue::from($key), JsValue::from($val)].iter().collect::<Array>() ),*
-            ].iter().collect::<Array>();
-            Object::from_entries(&entries).unwrap()
+pub(crate) const CHANGED: &str = "csvchange";
+pub(crate) const SELECTED: &str = "csvselect";
+pub(crate) const LAYOUT: &str = "csvlayout";
+
+/// Widest automatic column width. Longer values end with an ellipsis.
+const MAX_WIDTH: usize = 480;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Selection {
+    pub(crate) header: bool,
+    pub(crate) rows: Vec<usize>,
+    pub(crate) cols: Vec<usize>,
+}
+
+impl Selection {
+    /// Selected rows as ranges of contiguous csv rows. A filter can split a selection into several.
+    pub(crate) fn row_blocks(&self) -> Vec<std::ops::Range<usize>> {
+        let mut blocks = Vec::<std::ops::Range<usize>>::new();
+        for &row in &self.rows {
+            match blocks.last_mut() {
+                Some(block) if block.end == row => block.end += 1,
+                _ => blocks.push(row..row + 1),
+            }
         }
+        blocks
+    }
+}
+
+/// Input placed over a header cell while its caption is edited.
+#[derive(Debug)]
+struct HeaderInput {
+    input: HtmlInputElement,
+    col: usize,
+    _listeners: Vec<EventListener>,
+}
+
+/// Text area placed over a cell while a multi-line value is edited.
+#[derive(Debug)]
+struct TextInput {
+    textarea: HtmlTextAreaElement,
+    address: (usize, usize),
+    cell: (usize, usize),
+    _listeners: Vec<EventListener>,
+}
+
+/// Editors and column types shared by all columns.
+#[derive(Debug, Clone)]
+struct Renderers {
+    editor: cheetah_grid::CellEditor,
+    number_editor: cheetah_grid::CellEditor,
+    date_editor: cheetah_grid::CellEditor,
+    check_editor: cheetah_grid::CheckEditor,
+    /// Dropdowns of the declared choice columns, rebuilt with the header.
+    menu_editors: Rc<RefCell<Vec<cheetah_grid::InlineMenuEditor>>>,
+    /// Inputs suggesting the values of the inferred choice columns, rebuilt with the header.
+    choice_editors: Rc<RefCell<Vec<cheetah_grid::CellEditor>>>,
+    read_only: Rc<Cell<bool>>,
+    text_column: cheetah_grid::HighlightColumn,
+    number_column: cheetah_grid::HighlightNumberColumn,
+}
+
+impl Renderers {
+    fn new(read_only: Rc<Cell<bool>>) -> Result<Self, JsValue> {
+        Ok(Self {
+            editor: cheetah_grid::CellEditor::new(Object::new())?,
+            number_editor: cheetah_grid::CellEditor::new(js! { "type" => "number" })?,
+            date_editor: cheetah_grid::CellEditor::new(js! { "type" => "date" })?,
+            check_editor: cheetah_grid::CheckEditor::new()?,
+            menu_editors: Rc::new(RefCell::new(vec![])),
+            choice_editors: Rc::new(RefCell::new(vec![])),
+            read_only,
+            text_column: cheetah_grid::HighlightColumn::new()?,
+            number_column: cheetah_grid::HighlightNumberColumn::new()?,
+        })
+    }
+
+    fn set_read_only(&self, val: bool) {
+        self.read_only.set(val);
+        self.editor.set_read_only(val);
+        self.number_editor.set_read_only(val);
+        self.date_editor.set_read_only(val);
+        self.check_editor.set_read_only(val);
+        for editor in self.menu_editors.borrow().iter() {
+            editor.set_read_only(val);
+        }
+        for editor in self.choice_editors.borrow().iter() {
+            editor.set_read_only(val);
+        }
+    }
+
+    /// Hands cells with line breaks to `open_text` in the text editors.
+    fn set_open_text(&self, open_text: &Function) {
+        self.editor.set_open_text(open_text);
+        for editor in self.choice_editors.borrow().iter() {
+            editor.set_open_text(open_text);
+        }
+    }
+
+    fn clear(&self) {
+        self.menu_editors.borrow_mut().clear();
+        self.choice_editors.borrow_mut().clear();
+    }
+
+    /// Text input suggesting the values of a column.
+    fn choice_editor(&self, choices: &[&str]) -> Result<JsValue, JsValue> {
+        let editor = cheetah_grid::CellEditor::new(js! {
+            "choices" => choices.iter().map(|&val| JsValue::from(val)).collect::<Array>(),
+            "readOnly" => self.read_only.get()
+        })?;
+        editor.set_open_text(self.editor.open_text().unchecked_ref());
+        self.choice_editors.borrow_mut().push(editor.clone());
+        Ok(editor.into())
+    }
+
+    /// Dropdown editor offering the values of a column.
+    fn menu_editor(&self, choices: &[&str]) -> Result<JsValue, JsValue> {
+        let options = choices.iter().map(|&val| js! {
+            "value" => val,
+            "label" => val
+        }).collect::<Array>();
+        let editor = cheetah_grid::InlineMenuEditor::new(js! {
+            "options" => options,
+            "readOnly" => self.read_only.get()
+        })?;
+        self.menu_editors.borrow_mut().push(editor.clone());
+        Ok(editor.into())
+    }
+
+    /// Editor for values of a column type. Only declared choices are limited to the values of the column.
+    fn editor(&self, kind: ColumnType, declared: bool, csv: &Csv, col: usize) -> JsValue {
+        let choice_editor = |choices: &[&str]| if declared {
+            self.menu_editor(choices)
+        } else {
+            self.choice_editor(choices)
+        };
+        match kind {
+            ColumnType::Number => self.number_editor.clone().into(),
+            ColumnType::Date => self.date_editor.clone().into(),
+            ColumnType::Boolean => self.check_editor.clone().into(),
+            ColumnType::Choice => choice_editor(&choices(csv.cols(col))).unwrap_or_else(|err| {
+                log::error!("{:?}", err);
+                self.editor.clone().into()
+            }),
+            ColumnType::Text => self.editor.clone().into(),
+        }
+    }
+}
+
+/// Handles to the grid shared with its event handlers.
+#[derive(Debug, Clone)]
+struct View {
+    grid: cheetah_grid::ListGrid,
+    renderers: Renderers,
+    columns: Rc<RefCell<Columns>>,
+    filters: Rc<RefCell<Filters>>,
+    /// Shown and total number of rows.
+    counts: Rc<Cell<(usize, usize)>>,
+    /// Csv rows shown by the grid, `None` when not filtered.
+    rows: Rc<RefCell<Option<Vec<usize>>>>,
+    header_input: Rc<RefCell<Option<HeaderInput>>>,
+    text_input: Rc<RefCell<Option<TextInput>>>,
+}
+
+impl View {
+    /// Csv column of a grid column.
+    fn csv_col(&self, col: usize, csv: &Csv) -> Option<usize> {
+        col.checked_sub(1).and_then(|col| self.columns.borrow().visible(csv.max_cols()).get(col).copied())
+    }
+
+    /// Csv row of a grid data row.
+    fn csv_row(&self, row: usize) -> Option<usize> {
+        match &*self.rows.borrow() {
+            Some(rows) => rows.get(row).copied(),
+            None => Some(row),
+        }
+    }
+
+    /// Csv columns shown from a grid column on, at most `count` of them.
+    fn csv_cols_from(&self, col: usize, count: usize, csv: &Csv) -> Vec<usize> {
+        let visible = self.columns.borrow().visible(csv.max_cols());
+        visible.into_iter().skip(col.saturating_sub(1)).take(count).collect()
+    }
+
+    /// Csv rows shown from a grid data row on, at most `count` of them.
+    fn csv_rows_from(&self, row: usize, count: usize, csv: &Csv) -> Vec<usize> {
+        match &*self.rows.borrow() {
+            Some(rows) => rows.iter().skip(row).take(count).copied().collect(),
+            None => (row..csv.rows()).take(count).collect(),
+        }
+    }
+
+    /// Grid data row of a csv row, if it is not filtered out.
+    fn grid_row(&self, row: usize) -> Option<usize> {
+        match &*self.rows.borrow() {
+            Some(rows) => rows.binary_search(&row).ok(),
+            None => Some(row),
+        }
+    }
+
+    fn refresh(&self, csv: &Csv) -> Result<(), JsValue> {
+        let filters = self.filters.borrow();
+        let columns = self.columns.borrow();
+        self.grid.set_header(&header(csv, &self.renderers, &columns, &filters))?;
+        self.grid.set_frozen_col_count(frozen_col_count(csv, &columns));
+        self.renderers.text_column.set_query(filters.quick());
+        self.renderers.number_column.set_query(filters.quick());
+        let rows = filters.rows(csv);
+        let length = rows.as_ref().map(Vec::len).unwrap_or_else(|| csv.rows());
+        self.counts.set((length, csv.rows()));
+        *self.rows.borrow_mut() = rows;
+        let data_source = self.grid.data_source()?;
+        data_source.set_length(length)?;
+        data_source.clear_cache()?;
+        self.grid.invalidate()
+    }
+
+    /// Widens an automatically sized column to fit an edited value.
+    fn grow_width(&self, col: usize, csv_col: usize, val: &str) -> Result<(), JsValue> {
+        if self.columns.borrow().get(csv_col).width.is_some() {
+            return Ok(())
+        }
+        let width = calc_width(std::iter::once(val)).min(MAX_WIDTH);
+        if self.grid.get_col_width(col)? < width as f64 {
+            self.grid.set_col_width(col, width)?;
+            self.grid.invalidate()?;
+        }
+        Ok(())
     }
 }
 
@@ -26,21 +250,50 @@ macro_rules! js {
 pub(crate) struct Grid {
     name: String,
 
+    element: Element,
+
     csv: Arc<Mutex<Csv>>,
 
-    #[allow(dead_code)]
-    grid: cheetah_grid::ListGrid,
+    view: View,
+
+    context_cell: Rc<Cell<Option<(usize, usize)>>>,
+
+    read_only: Rc<Cell<bool>>,
 
     #[allow(dead_code)]
     get_record: Closure<dyn FnMut(usize) -> Promise>,
 
     #[allow(dead_code)]
     on_changed: Closure<dyn FnMut(Object)>,
+
+    #[allow(dead_code)]
+    on_context_menu: Closure<dyn FnMut(Object)>,
+
+    #[allow(dead_code)]
+    on_dblclick: Closure<dyn FnMut(Object)>,
+
+    #[allow(dead_code)]
+    on_open_text: Closure<dyn FnMut(usize, usize) -> bool>,
+
+    #[allow(dead_code)]
+    on_selected: Closure<dyn FnMut(Object)>,
+
+    #[allow(dead_code)]
+    on_mousedown: Closure<dyn FnMut(Object)>,
+
+    #[allow(dead_code)]
+    on_mouseup: Closure<dyn FnMut(Object)>,
+
+    #[allow(dead_code)]
+    on_resize: Closure<dyn FnMut(Object)>,
+
+    #[allow(dead_code)]
+    on_paste: EventListener,
 }
 
 impl Grid {
-    pub(crate) fn new(element: Element, name: String, data: &str, use_header: bool) -> Result<Grid, JsValue> {
-        load(element, name, data, use_header)
+    pub(crate) fn new(element: Element, name: String, csv: Csv) -> Result<Grid, JsValue> {
+        load(element, name, csv)
     }
 
     pub(crate) fn csv(&self) -> Arc<Mutex<Csv>> {
@@ -51,8 +304,183 @@ impl Grid {
         &self.name
     }
 
+    pub(crate) fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     pub(crate) fn grid(&self) -> &cheetah_grid::ListGrid {
-        &self.grid
+        &self.view.grid
+    }
+
+    pub(crate) fn columns(&self) -> &RefCell<Columns> {
+        &self.view.columns
+    }
+
+    pub(crate) fn filters(&self) -> &RefCell<Filters> {
+        &self.view.filters
+    }
+
+    /// Shown and total number of rows as of the last refresh.
+    pub(crate) fn counts(&self) -> (usize, usize) {
+        self.view.counts.get()
+    }
+
+    /// Csv rows in display order.
+    pub(crate) fn visible_rows(&self, csv: &Csv) -> Vec<usize> {
+        self.view.rows.borrow().clone().unwrap_or_else(|| (0..csv.rows()).collect())
+    }
+
+    /// Csv columns in display order.
+    pub(crate) fn visible_cols(&self, csv: &Csv) -> Vec<usize> {
+        self.view.columns.borrow().visible(csv.max_cols())
+    }
+
+    /// Csv cell of the cursor, if it is on a data cell.
+    pub(crate) fn cursor(&self, csv: &Csv) -> Result<Option<(usize, usize)>, JsValue> {
+        let (col, row) = select_address(&self.view.grid)?;
+        let row = row.checked_sub(1).and_then(|row| self.view.csv_row(row));
+        Ok(row.zip(self.view.csv_col(col, csv)))
+    }
+
+    /// Moves the cursor to a csv cell and scrolls it into view.
+    pub(crate) fn select_cell(&self, csv: &Csv, row: usize, col: usize) -> Result<(), JsValue> {
+        let visible = self.visible_cols(csv);
+        let col = visible.iter().position(|&c| c == col).ok_or("the column is hidden")? + 1;
+        let row = self.view.grid_row(row).ok_or("the row is filtered out")? + 1;
+        self.view.grid.selection()?.set_select(&js! { "col" => col, "row" => row });
+        self.view.grid.make_visible_cell(col, row)?;
+        self.view.grid.focus_cell(col, row)
+    }
+
+    /// Selects a csv cell and scrolls its row to the top of the view.
+    pub(crate) fn go_to(&self, csv: &Csv, row: usize, col: usize) -> Result<(), JsValue> {
+        self.select_cell(csv, row, col)?;
+        let (col, row) = select_address(&self.view.grid)?;
+        let top = rect_value(&self.view.grid.get_cell_rect(col, row)?, "top");
+        let header = rect_value(&self.view.grid.get_cell_rect(col, 0)?, "height");
+        self.view.grid.set_scroll_top(top - header);
+        Ok(())
+    }
+
+    /// Csv column of the header cell the context menu was last opened on.
+    pub(crate) fn take_context_column(&self, csv: &Csv) -> Option<usize> {
+        match self.context_cell.take() {
+            Some((col, 0)) => self.view.csv_col(col, csv),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn selection(&self, csv: &Csv) -> Result<Selection, JsValue> {
+        let range = self.view.grid.selection()?.range();
+        #[allow(unused_unsafe)]
+        let (start, end) = unsafe {
+            (Reflect::get(&range, &"start".into())?, Reflect::get(&range, &"end".into())?)
+        };
+        let (start_col, start_row) = cell_address(&start)?;
+        let (end_col, end_row) = cell_address(&end)?;
+        let (start_col, end_col) = (start_col.min(end_col), start_col.max(end_col));
+        let (start_row, end_row) = (start_row.min(end_row), start_row.max(end_row));
+
+        let visible = self.view.columns.borrow().visible(csv.max_cols());
+        Ok(Selection {
+            header: start_row == 0,
+            rows: (start_row.max(1) - 1 .. end_row).filter_map(|row| self.view.csv_row(row)).collect(),
+            cols: visible.get(start_col.max(1) - 1 .. end_col.min(visible.len())).unwrap_or(&[]).to_vec(),
+        })
+    }
+
+    /// Shifts the column settings and conditions along with columns changed by undo or redo.
+    pub(crate) fn follow_columns(&self, changes: &[ColumnChange]) {
+        let mut columns = self.view.columns.borrow_mut();
+        let mut filters = self.view.filters.borrow_mut();
+        for change in changes {
+            match change {
+                ColumnChange::Insert(col) => {
+                    columns.insert(*col);
+                    filters.insert_col(*col);
+                }
+                ColumnChange::Remove(col) => {
+                    columns.remove(*col);
+                    filters.remove_col(*col);
+                }
+                ColumnChange::Reorder(order) => {
+                    columns.reorder(order);
+                    filters.reorder_cols(order);
+                }
+            }
+        }
+    }
+
+    /// Stores the column layout under the file name.
+    pub(crate) fn save_layout(&self) -> Result<(), JsValue> {
+        self.view.columns.borrow().save(&self.name)
+    }
+
+    /// Moves the csv columns into display order if saving is set to apply it.
+    /// The display order then matches the file and is no longer stored.
+    pub(crate) fn apply_save_order(&self, csv: &mut Csv) -> Result<(), JsValue> {
+        let order = self.view.columns.borrow().save_order(csv.max_cols());
+        if let Some(order) = order {
+            csv.reorder_cols(&order);
+            self.view.columns.borrow_mut().reorder(&order);
+            self.view.filters.borrow_mut().reorder_cols(&order);
+            self.save_layout()?;
+            self.view.refresh(csv)?;
+        }
+        Ok(())
+    }
+
+    /// Edits the cell of the cursor in the multi-line editor.
+    pub(crate) fn edit_text(&self, csv: &Csv) -> Result<(), JsValue> {
+        if self.read_only.get() {
+            return Ok(())
+        }
+        let (col, row) = select_address(&self.view.grid)?;
+        let cell = row.checked_sub(1).and_then(|row| self.view.csv_row(row)).zip(self.view.csv_col(col, csv));
+        let (csv_row, csv_col) = cell.ok_or("Select a cell first.")?;
+        let value = csv.val(csv_row, csv_col).unwrap_or("");
+        edit_text(&self.view, &self.element, &self.csv, (col, row), (csv_row, csv_col), value)
+    }
+
+    pub(crate) fn read_only(&self) -> bool {
+        self.read_only.get()
+    }
+
+    pub(crate) fn set_read_only(&mut self, val: bool) {
+        self.view.renderers.set_read_only(val);
+    }
+
+    /// Redraws the grid after the csv was modified outside of the grid.
+    pub(crate) fn refresh(&self, csv: &Csv) -> Result<(), JsValue> {
+        self.view.refresh(csv)
+    }
+}
+
+/// Frozen grid columns: the row number column and the frozen csv columns.
+fn frozen_col_count(csv: &Csv, columns: &Columns) -> usize {
+    columns.frozen().min(columns.visible(csv.max_cols()).len()) + 1
+}
+
+fn select_address(grid: &cheetah_grid::ListGrid) -> Result<(usize, usize), JsValue> {
+    cell_address(&grid.selection()?.select())
+}
+
+fn rect_value(rect: &Object, key: &str) -> f64 {
+    #[allow(unused_unsafe)]
+    let value = unsafe {
+        Reflect::get(rect, &key.into()).ok().and_then(|v| v.as_f64())
+    };
+    value.unwrap_or(0.0)
+}
+
+fn cell_address(cell: &JsValue) -> Result<(usize, usize), JsValue> {
+    #[allow(unused_unsafe)]
+    let (col, row) = unsafe {
+        (Reflect::get(cell, &"col".into())?, Reflect::get(cell, &"row".into())?)
+    };
+    match (col.as_f64(), row.as_f64()) {
+        (Some(col), Some(row)) => Ok((col as usize, row as usize)),
+        _ => Err("invalid cell address".into()),
     }
 }
 
@@ -61,39 +489,227 @@ fn calc_width<'a>(iter: impl Iterator<Item=&'a str>) -> usize {
     8 + (nwidth + 1) * 8 + 8
 }
 
-fn header(csv: &Csv, editor: &cheetah_grid::InlineInputEditor) -> Array {
+/// Width fitting the values and the caption of a column, up to `MAX_WIDTH`.
+fn fit_width(col: usize, caption: &str, csv: &Csv) -> usize {
+    calc_width(csv.cols(col).chain(std::iter::once(caption))).min(MAX_WIDTH)
+}
+
+fn header(csv: &Csv, renderers: &Renderers, columns: &Columns, filters: &Filters) -> Array {
+    renderers.clear();
     vec![js! {
         "field" => "n",
         "caption" => "#",
         "sort" => true,
         "width" => "40px", // 8 + (2 + 1) * 8 + 8
         "columnType" => "number"
-    }].into_iter().chain((0..csv.max_cols()).map(|i| js! {
-        "field" => format!("c{}", i),
-        "caption" => csv.header(i).unwrap_or(&format!("{}", i)),
-        "action" => editor.clone(),
-        "width" => format!("{}px", calc_width(csv.cols(i))),
-        "minWidth" => "64px",
-        "sort" => true,
-        "columnType" => if csv.cols(i).all(|v| v.parse::<f64>().is_ok()) { "number" } else { "text" }
+    }].into_iter().chain(columns.visible(csv.max_cols()).into_iter().map(|i| {
+        let column = columns.get(i);
+        let kind = column.kind.unwrap_or_else(|| ColumnType::infer(csv.cols(i)));
+        let caption = csv.header(i).map(ToString::to_string).unwrap_or_else(|| format!("{}", i));
+        let caption = if filters.is_filtered(i) { format!("\u{25bc} {}", caption) } else { caption };
+        let width = column.width.unwrap_or_else(|| fit_width(i, &caption, csv));
+        js! {
+            "field" => format!("c{}", i),
+            "caption" => caption,
+            "action" => renderers.editor(kind, column.kind.is_some(), csv, i),
+            "width" => format!("{}px", width),
+            "minWidth" => "64px",
+            "style" => js! { "textOverflow" => "ellipsis" },
+            "sort" => true,
+            "columnType" => match kind {
+                ColumnType::Number => JsValue::from(&renderers.number_column),
+                ColumnType::Boolean => JsValue::from("check"),
+                _ => JsValue::from(&renderers.text_column),
+            }
+        }
     })).collect()
 }
 
-fn load(element: Element, name: String, data: &str, use_header: bool) -> Result<Grid, JsValue> {
-    let editor = cheetah_grid::InlineInputEditor::new()?;
-    let csv = csvparser::Csv::parse(data, use_header)
-        .map_err(|e| format!("failed to parse csv {}", e))?;
-    let header = header(&csv, &editor);
+fn edit_header(
+    view: &View,
+    element: &Element,
+    csv: &Arc<Mutex<Csv>>,
+    (col, csv_col): (usize, usize),
+    caption: &str) -> Result<(), JsValue> {
+
+    if view.header_input.borrow().is_some() {
+        return Ok(())
+    }
+    let rect = view.grid.get_cell_relative_rect(col, 0)?;
+    let [left, top, width, height] = ["left", "top", "width", "height"].map(|key| rect_value(&rect, key));
+
+    let document = element.owner_document().ok_or("no owner document found")?;
+    let input = document.create_element("input")?.dyn_into::<HtmlInputElement>()?;
+    input.set_class_name("app-header-input");
+    input.set_attribute("style", &format!(
+            "left: {}px; top: {}px; width: {}px; height: {}px;", left, top, width, height))?;
+    input.set_value(caption);
+    element.append_child(&input)?;
+
+    let commit = {
+        let view = view.clone();
+        let element = element.clone();
+        let csv = csv.clone();
+        move |apply: bool| spawn_local(commit_header(view.clone(), element.clone(), csv.clone(), apply))
+    };
+    let listeners = vec![
+        EventListener::new(&input, "keydown", {
+            let commit = commit.clone();
+            move |event| {
+                let event = if let Some(event) = event.dyn_ref::<KeyboardEvent>() { event } else { return };
+                event.stop_propagation();
+                match event.key().as_ref() {
+                    "Enter" => commit(true),
+                    "Escape" => commit(false),
+                    _ => {}
+                }
+            }
+        }),
+        EventListener::new(&input, "blur", move |_| commit(true)),
+    ];
+    *view.header_input.borrow_mut() = Some(HeaderInput { input: input.clone(), col: csv_col, _listeners: listeners });
+
+    input.focus()?;
+    input.select();
+    Ok(())
+}
+
+fn edit_text(
+    view: &View,
+    element: &Element,
+    csv: &Arc<Mutex<Csv>>,
+    (col, row): (usize, usize),
+    cell: (usize, usize),
+    value: &str) -> Result<(), JsValue> {
+
+    if view.text_input.borrow().is_some() {
+        return Ok(())
+    }
+    let rect = view.grid.get_cell_relative_rect(col, row)?;
+    let [left, top, width] = ["left", "top", "width"].map(|key| rect_value(&rect, key));
+
+    let document = element.owner_document().ok_or("no owner document found")?;
+    let textarea = document.create_element("textarea")?.dyn_into::<HtmlTextAreaElement>()?;
+    textarea.set_class_name("app-text-input");
+    textarea.set_attribute("style", &format!("left: {}px; top: {}px; min-width: {}px;", left, top, width))?;
+    textarea.set_value(value);
+    element.append_child(&textarea)?;
+
+    let commit = {
+        let view = view.clone();
+        let element = element.clone();
+        let csv = csv.clone();
+        move |apply: bool| spawn_local(commit_text(view.clone(), element.clone(), csv.clone(), apply))
+    };
+    let listeners = vec![
+        EventListener::new(&textarea, "keydown", {
+            let commit = commit.clone();
+            move |event| {
+                let event = if let Some(event) = event.dyn_ref::<KeyboardEvent>() { event } else { return };
+                event.stop_propagation();
+                match event.key().as_ref() {
+                    // Shift+Enter inserts a line break
+                    "Enter" if !event.shift_key() => {
+                        event.prevent_default();
+                        commit(true)
+                    }
+                    "Escape" => commit(false),
+                    _ => {}
+                }
+            }
+        }),
+        EventListener::new(&textarea, "blur", move |_| commit(true)),
+    ];
+    *view.text_input.borrow_mut() = Some(TextInput { textarea: textarea.clone(), address: (col, row), cell, _listeners: listeners });
+
+    textarea.focus()?;
+    textarea.select();
+    Ok(())
+}
+
+async fn commit_text(view: View, element: Element, csv: Arc<Mutex<Csv>>, apply: bool) {
+    let text_input = view.text_input.borrow_mut().take();
+    if let Some(TextInput { textarea, address, cell: (row, col), .. }) = text_input {
+        textarea.remove();
+        let mut csv = csv.lock().await;
+        let value = textarea.value();
+        let original = csv.val(row, col).unwrap_or("");
+        // the text area turns line breaks into LF, so only a changed text is written back
+        if apply && original.replace("\r\n", "\n").replace('\r', "\n") != value {
+            let eol = line_break(original).unwrap_or_else(|| csv.eol());
+            csv.set_val(row, col, value.replace('\n', eol));
+            if let Err(err) = view.refresh(&csv) {
+                log::error!("{:?}", err);
+            }
+            notify_changed(&element);
+        }
+        view.grid.focus_cell(address.0, address.1).ok();
+    }
+}
+
+/// Line break used in a value, if it has any.
+fn line_break(val: &str) -> Option<&'static str> {
+    let index = val.find(&['\r', '\n'][..])?;
+    Some(match &val[index..] {
+        rest if rest.starts_with("\r\n") => "\r\n",
+        rest if rest.starts_with('\r') => "\r",
+        _ => "\n",
+    })
+}
+
+async fn commit_header(view: View, element: Element, csv: Arc<Mutex<Csv>>, apply: bool) {
+    let header_input = view.header_input.borrow_mut().take();
+    if let Some(HeaderInput { input, col, .. }) = header_input {
+        input.remove();
+        let value = input.value();
+        let mut csv = csv.lock().await;
+        if apply && csv.header(col).unwrap_or("") != value {
+            csv.set_header(col, value);
+            if let Err(err) = view.refresh(&csv) {
+                log::error!("{:?}", err);
+            }
+            notify_changed(&element);
+        }
+    }
+}
+
+fn notify_changed(element: &Element) {
+    notify(element, CHANGED)
+}
+
+fn notify(element: &Element, type_: &str) {
+    let init = EventInit::new();
+    init.set_bubbles(true);
+    if let Ok(event) = Event::new_with_event_init_dict(type_, &init) {
+        element.dispatch_event(&event).ok();
+    }
+}
+
+fn load(element: Element, name: String, csv: Csv) -> Result<Grid, JsValue> {
+    let read_only = Rc::new(Cell::new(false));
+    let renderers = Renderers::new(read_only.clone())?;
+    let columns = Rc::new(RefCell::new(Columns::load(&name)));
+    let filters = Rc::new(RefCell::new(Filters::default()));
+    let header = header(&csv, &renderers, &columns.borrow(), &filters.borrow());
+    let frozen = frozen_col_count(&csv, &columns.borrow());
+    let counts = Rc::new(Cell::new((csv.rows(), csv.rows())));
+    let rows = Rc::new(RefCell::new(None::<Vec<usize>>));
 
     let length = csv.rows() as u32;
     let csv = Arc::new(Mutex::new(csv));
 
     let get_record = {
         let csv = csv.clone();
-        Closure::wrap(Box::new(move |index| {
+        let rows = rows.clone();
+        Closure::wrap(Box::new(move |index: usize| {
             let csv = csv.clone();
+            let index = match &*rows.borrow() {
+                Some(rows) => rows.get(index).copied(),
+                None => Some(index),
+            };
             future_to_promise(async move {
                 let csv = csv.lock().await;
+                let index = if let Some(index) = index { index } else { return Ok(Object::new().into()) };
                 let row = js! {
                     "n" => format!("{}", index)
                 };
@@ -114,9 +730,10 @@ fn load(element: Element, name: String, data: &str, use_header: bool) -> Result<
     })?;
 
     let opt = js! {
-        "parentElement" => element,
+        "parentElement" => element.clone(),
         "header" => &header,
         "dataSource" => &data_source,
+        "frozenColCount" => frozen,
         "font" => "16px monospace",
         "allowRangePaste" => true,
         "keyboardOptions" => &js! {
@@ -127,33 +744,235 @@ fn load(element: Element, name: String, data: &str, use_header: bool) -> Result<
         }
     };
     let grid = cheetah_grid::ListGrid::new(Some(&opt))?;
+    let view = View { grid, renderers, columns, filters, counts, rows, header_input: Rc::new(RefCell::new(None)), text_input: Rc::new(RefCell::new(None)) };
 
     let on_changed = {
+        let view = view.clone();
         let csv = csv.clone();
+        let element = element.clone();
         Closure::wrap(Box::new(move |obj: Object| {
             #[allow(unused_unsafe)]
-            let (row, col, value) = unsafe {
+            let (row, field, value) = unsafe {
                 let row = Reflect::get(&obj, &"row".into()).ok();
-                let col = Reflect::get(&obj, &"col".into()).ok();
+                let field = Reflect::get(&obj, &"field".into()).ok();
                 let value = Reflect::get(&obj, &"value".into()).ok();
-                (row, col, value)
+                (row, field, value)
             };
             let row = row.as_ref().and_then(JsValue::as_f64).map(|f| f as usize);
-            let col = col.as_ref().and_then(JsValue::as_f64).map(|f| f as usize);
+            let col = field.as_ref().and_then(JsValue::as_string)
+                .and_then(|field| field.strip_prefix('c').and_then(|col| col.parse::<usize>().ok()));
             let value = value.as_ref().and_then(JsValue::as_string);
 
+            let row = row.and_then(|row| row.checked_sub(1)).and_then(|row| view.csv_row(row));
+            let grid_col = cell_address(&obj).ok().map(|(col, _)| col);
+
             if let (Some(row), Some(col), Some(val)) = (row, col, value) {
-                if row > 0 && col > 0 {
+                let view = view.clone();
+                let csv = csv.clone();
+                let element = element.clone();
+                spawn_local(async move {
+                    if let Some(grid_col) = grid_col {
+                        if let Err(err) = view.grow_width(grid_col, col, &val) {
+                            log::error!("{:?}", err);
+                        }
+                    }
+                    let mut csv = csv.lock().await;
+                    if csv.set_val(row, col, val) {
+                        notify_changed(&element);
+                    }
+                })
+            }
+        }) as Box<dyn FnMut(Object)>)
+    };
+    view.grid.listen(&cheetah_grid::CHANGED_VALUE, &on_changed).unwrap();
+
+    let context_cell = Rc::new(Cell::new(None));
+    let on_context_menu = {
+        let context_cell = context_cell.clone();
+        Closure::wrap(Box::new(move |obj: Object| {
+            context_cell.set(cell_address(&obj).ok());
+        }) as Box<dyn FnMut(Object)>)
+    };
+    cheetah_grid::CONTEXTMENU_CELL.with(|event| view.grid.listen(event, &on_context_menu))?;
+
+    let on_dblclick = {
+        let view = view.clone();
+        let csv = csv.clone();
+        let read_only = read_only.clone();
+        let element = element.clone();
+        Closure::wrap(Box::new(move |obj: Object| {
+            match cell_address(&obj) {
+                Ok((col, 0)) if col > 0 && !read_only.get() => {
+                    let view = view.clone();
                     let csv = csv.clone();
+                    let element = element.clone();
                     spawn_local(async move {
-                        let mut csv = csv.lock().await;
-                        csv.set_val(row - 1, col - 1, val);
+                        let caption = {
+                            let csv = csv.lock().await;
+                            view.csv_col(col, &csv).map(|c| (c, csv.header(c).unwrap_or("").to_string()))
+                        };
+                        if let Some((csv_col, caption)) = caption {
+                            if let Err(err) = edit_header(&view, &element, &csv, (col, csv_col), &caption) {
+                                log::error!("{:?}", err);
+                            }
+                        }
                     })
                 }
+                _ => {}
             }
         }) as Box<dyn FnMut(Object)>)
     };
-    grid.listen(&cheetah_grid::CHANGED_VALUE, &on_changed).unwrap();
+    cheetah_grid::DBLCLICK_CELL.with(|event| view.grid.listen(event, &on_dblclick))?;
+
+    // values with line breaks are edited in a text area instead of the inline input
+    let on_open_text = {
+        let view = view.clone();
+        let csv = csv.clone();
+        let read_only = read_only.clone();
+        let element = element.clone();
+        Closure::wrap(Box::new(move |col: usize, row: usize| {
+            if read_only.get() {
+                return false
+            }
+            let locked = if let Some(csv) = csv.try_lock() { csv } else { return false };
+            let cell = row.checked_sub(1).and_then(|row| view.csv_row(row)).zip(view.csv_col(col, &locked));
+            let value = cell.and_then(|(r, c)| locked.val(r, c)).filter(|val| val.contains(&['\r', '\n'][..]));
+            match (cell, value) {
+                (Some(cell), Some(value)) => {
+                    if let Err(err) = edit_text(&view, &element, &csv, (col, row), cell, value) {
+                        log::error!("{:?}", err);
+                    }
+                    true
+                }
+                _ => false,
+            }
+        }) as Box<dyn FnMut(usize, usize) -> bool>)
+    };
+    view.renderers.set_open_text(on_open_text.as_ref().unchecked_ref());
+
+    let on_selected = {
+        let element = element.clone();
+        Closure::wrap(Box::new(move |_: Object| {
+            notify(&element, SELECTED);
+        }) as Box<dyn FnMut(Object)>)
+    };
+    cheetah_grid::SELECTED_CELL.with(|event| view.grid.listen(event, &on_selected))?;
+
+    // a header dragged onto another header moves its column there
+    let drag_col = Rc::new(Cell::new(None));
+    let on_mousedown = {
+        let drag_col = drag_col.clone();
+        Closure::wrap(Box::new(move |obj: Object| {
+            drag_col.set(match cell_address(&obj) {
+                Ok((col, 0)) if col > 0 => Some(col),
+                _ => None,
+            });
+        }) as Box<dyn FnMut(Object)>)
+    };
+    cheetah_grid::MOUSEDOWN_CELL.with(|event| view.grid.listen(event, &on_mousedown))?;
+
+    // resized widths are kept when the header is rebuilt
+    let on_resize = {
+        let view = view.clone();
+        let csv = csv.clone();
+        let drag_col = drag_col.clone();
+        Closure::wrap(Box::new(move |obj: Object| {
+            drag_col.set(None);
+            #[allow(unused_unsafe)]
+            let col = unsafe {
+                Reflect::get(&obj, &"col".into()).ok().and_then(|col| col.as_f64())
+            };
+            let col = match col {
+                Some(col) if col >= 1.0 => col as usize,
+                _ => return,
+            };
+            let view = view.clone();
+            let csv = csv.clone();
+            spawn_local(async move {
+                let csv = csv.lock().await;
+                if let (Some(csv_col), Ok(width)) = (view.csv_col(col, &csv), view.grid.get_col_width(col)) {
+                    view.columns.borrow_mut().get_mut(csv_col).width = Some(width as usize);
+                }
+            })
+        }) as Box<dyn FnMut(Object)>)
+    };
+    cheetah_grid::RESIZE_COLUMN.with(|event| view.grid.listen(event, &on_resize))?;
+
+    let on_mouseup = {
+        let view = view.clone();
+        let csv = csv.clone();
+        let element = element.clone();
+        Closure::wrap(Box::new(move |obj: Object| {
+            let (from, to) = match (drag_col.take(), cell_address(&obj)) {
+                (Some(from), Ok((to, 0))) if to > 0 && to != from => (from, to),
+                _ => return,
+            };
+            let view = view.clone();
+            let csv = csv.clone();
+            let element = element.clone();
+            spawn_local(async move {
+                let csv = csv.lock().await;
+                if let (Some(col), Some(target)) = (view.csv_col(from, &csv), view.csv_col(to, &csv)) {
+                    view.columns.borrow_mut().move_col(col, target, csv.max_cols());
+                    if let Err(err) = view.refresh(&csv) {
+                        log::error!("{:?}", err);
+                    }
+                    notify(&element, LAYOUT);
+                }
+            })
+        }) as Box<dyn FnMut(Object)>)
+    };
+    cheetah_grid::MOUSEUP_CELL.with(|event| view.grid.listen(event, &on_mouseup))?;
+
+    let on_paste = {
+        let element = element.clone();
+        let view = view.clone();
+        let csv = csv.clone();
+        let read_only = read_only.clone();
+        let opt = EventListenerOptions { phase: EventListenerPhase::Capture, passive: false };
+        EventListener::new_with_options(&element.clone(), "paste", opt, move |event| {
+            if read_only.get() {
+                return
+            }
+            let (col, row) = match select_address(&view.grid) {
+                Ok((col, row)) if col > 0 && row > 0 => (col, row),
+                _ => return,
+            };
+            let text = event.dyn_ref::<ClipboardEvent>()
+                .and_then(ClipboardEvent::clipboard_data)
+                .and_then(|data| data.get_data("text/plain").ok());
+            let values = match text.as_deref().map(|text| Csv::parse_with_delimiter(text, false, '\t')) {
+                Some(Ok(values)) => (0..values.rows())
+                    .map(|r| values.vals(r).map(String::from).collect::<Vec<_>>())
+                    .collect::<Vec<_>>(),
+                _ => return,
+            };
+            // a single value is pasted by the grid itself
+            if values.len() < 2 && values.iter().all(|line| line.len() < 2) {
+                return
+            }
+            event.prevent_default();
+            event.stop_propagation();
+
+            let view = view.clone();
+            let csv = csv.clone();
+            let element = element.clone();
+            spawn_local(async move {
+                let mut csv = csv.lock().await;
+                // values only go to rows the filter shows and columns in display order
+                let rows = view.csv_rows_from(row - 1, values.len(), &csv);
+                let width = values.iter().map(Vec::len).max().unwrap_or(0);
+                let cols = view.csv_cols_from(col, width, &csv);
+                if !cols.is_empty() {
+                    csv.paste(&rows, &cols, &values);
+                    if let Err(err) = view.refresh(&csv) {
+                        log::error!("{:?}", err);
+                    }
+                    notify_changed(&element);
+                }
+            })
+        })
+    };
 
-    Ok(Grid { get_record, name, csv, grid, on_changed, })
+    Ok(Grid { get_record, name, element, csv, view, context_cell, read_only, on_changed, on_context_menu, on_dblclick, on_open_text, on_selected, on_mousedown, on_mouseup, on_resize, on_paste })
 }
@@ -0,0 +1,110 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen::closure::Closure;
+use web_sys::{
+    Window,
+    IdbDatabase,
+    IdbObjectStore,
+    IdbRequest,
+    IdbTransaction,
+    IdbTransactionMode,
+};
+use gloo::events::EventListener;
+use futures::channel::oneshot;
+
+const NAME: &str = "my-csv-editor";
+const VERSION: u32 = 2;
+
+pub(crate) const SESSION: &str = "session";
+pub(crate) const RECENT: &str = "recent";
+
+const STORES: &[&str] = &[SESSION, RECENT];
+
+async fn wait(target: &web_sys::EventTarget, ok: &'static str, errors: &[&'static str]) -> bool {
+    let (tx, rx) = oneshot::channel();
+    let tx = Rc::new(RefCell::new(Some(tx)));
+
+    let mut listeners = vec![];
+    let events = std::iter::once((ok, true)).chain(errors.iter().map(|e| (*e, false)));
+    for (event, result) in events {
+        let tx = tx.clone();
+        listeners.push(EventListener::once(target, event, move |_| {
+            if let Some(tx) = tx.borrow_mut().take() {
+                tx.send(result).ok();
+            }
+        }));
+    }
+    rx.await.unwrap_or(false)
+}
+
+async fn request(request: IdbRequest) -> Result<JsValue, JsValue> {
+    if wait(&request, "success", &["error"]).await {
+        request.result()
+    } else {
+        Err(request.error()?.map(JsValue::from).unwrap_or_else(|| "indexeddb request failed".into()))
+    }
+}
+
+pub(crate) async fn open(window: &Window) -> Result<IdbDatabase, JsValue> {
+    let factory = window.indexed_db()?.ok_or("indexeddb not supported")?;
+    let open_request = factory.open_with_u32(NAME, VERSION)?;
+
+    let _upgrade = {
+        let db_request = open_request.clone();
+        EventListener::new(&open_request, "upgradeneeded", move |_| {
+            let db = db_request.result().ok().and_then(|db| db.dyn_into::<IdbDatabase>().ok());
+            if let Some(db) = db {
+                for store in STORES {
+                    if !db.object_store_names().contains(store) {
+                        if let Err(err) = db.create_object_store(store) {
+                            log::error!("{:?}", err);
+                        }
+                    }
+                }
+            }
+        })
+    };
+
+    // another tab with an older version open keeps the upgrade blocked
+    if !wait(&open_request, "success", &["error", "blocked"]).await {
+        let late = open_request.clone();
+        EventListener::once(&open_request, "success", move |_| {
+            if let Some(db) = late.result().ok().and_then(|db| db.dyn_into::<IdbDatabase>().ok()) {
+                db.close();
+            }
+        }).forget();
+        return Err(open_request.error().ok().flatten().map(JsValue::from)
+            .unwrap_or_else(|| "indexeddb is blocked by another tab".into()))
+    }
+    let db = open_request.result()?.dyn_into::<IdbDatabase>()?;
+
+    // let a newer version in another tab upgrade the database
+    let close = Closure::once_into_js({
+        let db = db.clone();
+        move || db.close()
+    });
+    db.set_onversionchange(Some(close.unchecked_ref()));
+    Ok(db)
+}
+
+pub(crate) fn transaction(db: &IdbDatabase, store: &str, write: bool) -> Result<(IdbTransaction, IdbObjectStore), JsValue> {
+    let mode = if write { IdbTransactionMode::Readwrite } else { IdbTransactionMode::Readonly };
+    let transaction = db.transaction_with_str_and_mode(store, mode)?;
+    let store = transaction.object_store(store)?;
+    Ok((transaction, store))
+}
+
+pub(crate) async fn complete(transaction: &IdbTransaction) -> Result<(), JsValue> {
+    if wait(transaction, "complete", &["error", "abort"]).await {
+        Ok(())
+    } else {
+        Err(transaction.error().map(JsValue::from).unwrap_or_else(|| "indexeddb transaction failed".into()))
+    }
+}
+
+pub(crate) async fn get_all(db: &IdbDatabase, store: &str) -> Result<js_sys::Array, JsValue> {
+    let (_, store) = transaction(db, store, false)?;
+    request(store.get_all()?).await?.dyn_into()
+}
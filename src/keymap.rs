@@ -0,0 +1,224 @@
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlElement, HtmlInputElement, KeyboardEvent};
+use gloo::storage::{LocalStorage, Storage};
+
+const STORAGE_KEY: &str = "keymap";
+
+/// Class of the hidden input cheetah-grid keeps focused while a cell is selected.
+const GRID_FOCUS_CONTROL: &str = ".cheetah-grid__focus-control";
+
+/// What an action operates on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Target {
+    App,
+    /// The selected rows, like the row menu.
+    Rows,
+    /// The column of the cursor, like the column menu.
+    Column,
+}
+
+pub(crate) struct Action {
+    pub(crate) name: &'static str,
+    pub(crate) title: &'static str,
+    pub(crate) target: Target,
+    /// Whether the shortcut also works while typing in a text field.
+    global: bool,
+    default_key: Option<&'static str>,
+}
+
+macro_rules! action {
+    ($name:expr, $title:expr, $target:ident) => {
+        action!($name, $title, $target, false, None)
+    };
+    ($name:expr, $title:expr, $target:ident, $global:expr, $key:expr) => {
+        Action { name: $name, title: $title, target: Target::$target, global: $global, default_key: $key }
+    };
+}
+
+/// All actions of the app. Row and column actions share their names with the context menus.
+pub(crate) const ACTIONS: &[Action] = &[
+    action!("open", "Open file", App, true, Some("Ctrl+O")),
+    action!("open_url", "Open URL", App, true, None),
+    action!("paste_new", "Paste as new file", App, true, None),
+    action!("save", "Save", App, true, Some("Ctrl+S")),
+    action!("save_as", "Save as", App, true, Some("Ctrl+Shift+S")),
+    action!("read_only", "Toggle read only", App),
+    action!("use_header", "Toggle header row", App),
+    action!("find", "Find and replace", App, true, Some("Ctrl+F")),
+    action!("quick_filter", "Quick filter", App, true, Some("Ctrl+Shift+F")),
+    action!("goto", "Go to cell", App, true, Some("Ctrl+G")),
+    action!("undo", "Undo", App, false, Some("Ctrl+Z")),
+    action!("redo", "Redo", App, false, Some("Ctrl+Y")),
+    action!("drawer", "Toggle navigation drawer", App),
+    action!("add_before", "Insert rows above", Rows, false, Some("Ctrl+Shift+Plus")),
+    action!("add_after", "Insert rows below", Rows),
+    action!("remove", "Remove rows", Rows, false, Some("Ctrl+Minus")),
+    action!("duplicate", "Duplicate rows", Rows, false, Some("Ctrl+D")),
+    action!("edit_text", "Edit cell in multi-line editor", Rows, false, Some("Ctrl+Shift+E")),
+    action!("move_up", "Move rows up", Rows),
+    action!("move_down", "Move rows down", Rows),
+    action!("promote_header", "Use row as header", Rows),
+    action!("demote_header", "Header to row", Rows),
+    action!("copy_tsv", "Copy as TSV", Rows),
+    action!("copy_csv", "Copy as CSV", Rows),
+    action!("copy_markdown", "Copy as Markdown", Rows),
+    action!("insert_left", "Insert column left", Column),
+    action!("insert_right", "Insert column right", Column),
+    action!("remove_column", "Remove column", Column),
+    action!("rename", "Rename header", Column),
+    action!("sort_asc", "Sort ascending", Column),
+    action!("sort_desc", "Sort descending", Column),
+    action!("filter", "Filter column", Column),
+    action!("clear_filter", "Clear filter", Column),
+    action!("clear_filters", "Clear all filters", Column),
+    action!("auto_fit", "Auto-fit column width", Column),
+    action!("auto_fit_all", "Auto-fit all columns", Column),
+    action!("hide", "Hide column", Column),
+    action!("show_all", "Show all columns", Column),
+    action!("freeze", "Freeze columns up to this one", Column),
+    action!("unfreeze", "Unfreeze columns", Column),
+    action!("move_left", "Move column left", Column),
+    action!("move_right", "Move column right", Column),
+    action!("reset_order", "Reset column order", Column),
+    action!("apply_order", "Toggle saving columns in display order", Column),
+    action!("type_text", "Column type: text", Column),
+    action!("type_number", "Column type: number", Column),
+    action!("type_boolean", "Column type: boolean", Column),
+    action!("type_choice", "Column type: choice", Column),
+    action!("type_date", "Column type: date", Column),
+    action!("palette", "Command palette", App, true, Some("Ctrl+Shift+P")),
+    action!("keymap", "Keyboard shortcuts", App, true, Some("Ctrl+/")),
+];
+
+pub(crate) fn find(name: &str) -> Option<&'static Action> {
+    ACTIONS.iter().find(|action| action.name == name)
+}
+
+/// Key of every action, persisted in localStorage.
+#[derive(Debug)]
+pub(crate) struct Keymap {
+    keys: Vec<(&'static str, Option<String>)>,
+}
+
+impl Keymap {
+    pub(crate) fn load() -> Self {
+        let mut keymap = Self::default();
+        let stored = LocalStorage::get::<Vec<(String, Option<String>)>>(STORAGE_KEY).unwrap_or_default();
+        for (action, key) in stored {
+            if let Some(entry) = keymap.keys.iter_mut().find(|(name, _)| *name == action) {
+                entry.1 = key;
+            }
+        }
+        keymap
+    }
+
+    pub(crate) fn save(&self) -> Result<(), JsValue> {
+        LocalStorage::set(STORAGE_KEY, &self.keys).map_err(|e| e.to_string().into())
+    }
+
+    pub(crate) fn key(&self, action: &str) -> Option<&str> {
+        self.keys.iter().find(|(name, _)| *name == action).and_then(|(_, key)| key.as_deref())
+    }
+
+    /// Binds `key` to `action`, unbinding it from any other action.
+    pub(crate) fn set(&mut self, action: &str, key: Option<String>) {
+        for entry in &mut self.keys {
+            if entry.0 == action {
+                entry.1 = key.clone();
+            } else if entry.1.is_some() && entry.1 == key {
+                entry.1 = None;
+            }
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Action bound to the pressed keys. Only global actions fire while typing.
+    pub(crate) fn action(&self, event: &KeyboardEvent) -> Option<&'static str> {
+        let combo = combo(event)?;
+        let editing = event.target().and_then(|t| t.dyn_into::<Element>().ok())
+            .map(|t| is_editable(&t))
+            .unwrap_or(false);
+        ACTIONS.iter()
+            .filter(|action| action.global || !editing)
+            .find(|action| self.key(action.name) == Some(combo.as_str()))
+            .map(|action| action.name)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let keys = ACTIONS.iter().map(|action| (action.name, action.default_key.map(ToString::to_string))).collect();
+        Self { keys }
+    }
+}
+
+/// Normalized name of the pressed keys such as `Ctrl+Shift+S`. Cmd counts as Ctrl.
+pub(crate) fn combo(event: &KeyboardEvent) -> Option<String> {
+    let key = match event.key().as_str() {
+        "Control" | "Shift" | "Alt" | "Meta" | "Dead" | "Unidentified" => return None,
+        "+" => "Plus".to_string(),
+        "-" => "Minus".to_string(),
+        " " => "Space".to_string(),
+        key if key.chars().count() == 1 => key.to_uppercase(),
+        key => key.to_string(),
+    };
+    let mut combo = String::new();
+    let modifiers = [
+        (event.ctrl_key() || event.meta_key(), "Ctrl"),
+        (event.alt_key(), "Alt"),
+        (event.shift_key(), "Shift"),
+    ];
+    for (_, name) in modifiers.iter().filter(|(pressed, _)| *pressed) {
+        combo.push_str(name);
+        combo.push('+');
+    }
+    combo.push_str(&key);
+    Some(combo)
+}
+
+fn is_editable(element: &Element) -> bool {
+    if element.matches(GRID_FOCUS_CONTROL).unwrap_or(false) {
+        return false
+    }
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        || element.dyn_ref::<HtmlElement>().map(HtmlElement::is_content_editable).unwrap_or(false)
+}
+
+/// Key field of the shortcut list receiving the event, if any.
+pub(crate) fn recording(event: &KeyboardEvent) -> Option<HtmlInputElement> {
+    if event.key() == "Tab" {
+        return None
+    }
+    event.target()
+        .and_then(|t| t.dyn_into::<HtmlInputElement>().ok())
+        .filter(|input| input.matches(".app-keymap [data-action]").unwrap_or(false))
+}
+
+pub(crate) fn render(table: &Element, keymap: &Keymap) -> Result<(), JsValue> {
+    let document = table.owner_document().ok_or("no owner document found")?;
+    table.set_text_content(None);
+
+    for action in ACTIONS {
+        let row = document.create_element("tr")?;
+
+        let title = document.create_element("td")?;
+        title.set_text_content(Some(action.title));
+        row.append_child(&title)?;
+
+        let cell = document.create_element("td")?;
+        let input = document.create_element("input")?.dyn_into::<HtmlInputElement>()?;
+        input.set_class_name("app-keymap__key");
+        input.set_read_only(true);
+        input.set_placeholder("None");
+        input.set_attribute("data-action", action.name)?;
+        input.set_value(keymap.key(action.name).unwrap_or(""));
+        cell.append_child(&input)?;
+        row.append_child(&cell)?;
+
+        table.append_child(&row)?;
+    }
+    Ok(())
+}
@@ -1,45 +1,265 @@
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{
+    Element,
     HtmlElement,
     HtmlInputElement,
     Event,
     MouseEvent,
+    KeyboardEvent,
     DragEvent,
     CustomEvent,
-    Url,
-    File,
-    FilePropertyBag,
+    CustomEventInit,
     DataTransfer,
+    BeforeUnloadEvent,
+    IdbDatabase,
+    UrlSearchParams,
 };
-use js_sys::{Array, Reflect, Error as JsError, Uint8Array};
-use futures::stream::StreamExt as _;
+use js_sys::{Reflect, Error as JsError};
+use futures::stream::{self, StreamExt as _};
+use gloo::timers::callback::Timeout;
+use gloo::timers::future::IntervalStream;
 use encoding::EncodingRef;
+use wasm_bindgen_futures::JsFuture;
+use csvparser::Csv;
+use gloo::events::{EventListener, EventListenerOptions};
+use gloo::dialogs::{confirm, prompt};
 
 use event_stream::EventStream;
 use env::Env;
+use document::Document;
+use sys::file_system_access::FileSystemFileHandle;
+use sys::clipboard;
+
+macro_rules! js {
+    ( $( $key:expr => $val:expr ),* ) => {
+        {
+            let entries = [
+                $( [
+                    ::wasm_bindgen::JsValue::from($key),
+                    ::wasm_bindgen::JsValue::from($val)
+                ].iter().collect::<::js_sys::Array>() ),*
+            ].iter().collect::<::js_sys::Array>();
+            ::js_sys::Object::from_entries(&entries).unwrap()
+        }
+    }
+}
 
 mod sys;
 mod event_stream;
 mod env;
 mod grid;
+mod document;
+mod tabs;
+mod file;
+mod idb;
+mod session;
+mod recent;
+mod copy;
+mod column;
+mod find;
+mod filter;
+mod keymap;
+mod palette;
+mod status;
+mod goto;
+
+const AUTOSAVE_INTERVAL: u32 = 30_000;
+const QUICK_FILTER_DELAY: u32 = 250;
+const QUICK_FILTER: &str = "quickfilter";
 
 struct State {
     env: Env,
-    grid: Option<grid::Grid>,
-    coder: Option<EncodingRef>,
+    documents: Vec<Document>,
+    active: Option<usize>,
+    unsaved: Rc<Cell<bool>>,
+    db: Option<IdbDatabase>,
+    untitled: usize,
+    menu_column: Option<usize>,
+    find_selection: Option<grid::Selection>,
+    filter_column: Option<usize>,
+    quick_filter_timer: Option<Timeout>,
+    keymap: Rc<RefCell<keymap::Keymap>>,
+    palette_index: usize,
 }
 
-async fn load_csv(file: &gloo::file::File, state: &mut State) -> Result<(), JsValue> {
-    use gloo::file::futures::read_as_bytes;
-    use encoding::label::encoding_from_whatwg_label;
+impl State {
+    fn current(&self) -> Option<&Document> {
+        self.active.and_then(|index| self.documents.get(index))
+    }
 
-    let bytes = read_as_bytes(file).await.map_err(|e| format!("failed to read file {}", e))?;
+    fn current_mut(&mut self) -> Option<&mut Document> {
+        let active = self.active;
+        active.and_then(move |index| self.documents.get_mut(index))
+    }
+
+    fn activate(&mut self, index: usize) -> Result<(), JsValue> {
+        for (i, doc) in self.documents.iter().enumerate() {
+            if i == index {
+                doc.element().remove_attribute("hidden")?;
+            } else {
+                doc.element().set_attribute("hidden", "")?;
+            }
+        }
+        self.active = Some(index);
+        if let Some(doc) = self.current() {
+            self.env.quick_filter().set_value(doc.grid().filters().borrow().quick());
+            let grid = doc.grid().grid();
+            grid.update_size()?;
+            grid.invalidate()?;
+        }
+        self.env.app_read_only().set_disabled(self.active.is_none());
+        self.env.app_save().set_disabled(self.active.is_none());
+        self.env.app_save_as().set_disabled(self.active.is_none());
+        self.render()
+    }
+
+    fn open(
+        &mut self,
+        name: String,
+        csv: Csv,
+        coder: Option<EncodingRef>,
+        handle: Option<FileSystemFileHandle>,
+        replace: Option<usize>) -> Result<usize, JsValue> {
+
+        let document = self.env.root().owner_document().ok_or("no owner document found")?;
+        let div = document.create_element("div")?;
+        self.env.documents().append_child(&div)?;
+
+        let grid = match grid::Grid::new(div.clone(), name, csv) {
+            Ok(grid) => grid,
+            Err(err) => {
+                div.remove();
+                return Err(err)
+            }
+        };
+        let doc = Document::new(div, grid, coder, handle);
+        if let Some(index) = replace {
+            let old = std::mem::replace(&mut self.documents[index], doc);
+            old.element().remove();
+            Ok(index)
+        } else {
+            self.documents.push(doc);
+            Ok(self.documents.len() - 1)
+        }
+    }
+
+    fn close(&mut self, index: usize) -> Result<(), JsValue> {
+        if index >= self.documents.len() {
+            return Ok(())
+        }
+        let doc = self.documents.remove(index);
+        doc.element().remove();
+
+        match self.active {
+            _ if self.documents.is_empty() => {
+                self.active = None;
+                self.env.app_read_only().set_disabled(true);
+                self.env.app_save().set_disabled(true);
+                self.env.app_save_as().set_disabled(true);
+                self.render()
+            }
+            Some(active) if active > index => self.activate(active - 1),
+            Some(active) if active == index => self.activate(index.min(self.documents.len() - 1)),
+            _ => self.render(),
+        }
+    }
+
+    async fn autosave(&mut self) -> Result<(), JsValue> {
+        let db = if let Some(db) = &self.db {
+            db
+        } else {
+            return Ok(())
+        };
+        let mut snapshots = vec![];
+        for doc in &self.documents {
+            if doc.dirty() {
+                snapshots.push(doc.snapshot().await);
+            }
+        }
+        session::save(db, &snapshots).await
+    }
+
+    async fn render_recent(&self) -> Result<(), JsValue> {
+        if let Some(db) = &self.db {
+            recent::render(self.env.recent(), &recent::load(db).await?)?;
+        }
+        Ok(())
+    }
 
-    let (encoding, _, _) = chardet::detect(&bytes);
+    async fn update_dirty(&mut self) -> Result<(), JsValue> {
+        let mut unsaved = false;
+        for doc in &mut self.documents {
+            unsaved |= doc.update_dirty().await;
+        }
+        self.unsaved.set(unsaved);
+        self.render()
+    }
+
+    fn report(&self, err: &JsValue) {
+        log::error!("{:?}", err);
+        if let Some(err) = err.dyn_ref::<JsError>() {
+            if let Some(err) = JsValue::from(err.message()).as_string() {
+                self.env.error().set_text_content(Some(&err));
+            }
+        } else if let Some(err) = err.as_string() {
+            self.env.error().set_text_content(Some(&err));
+        }
+    }
+
+    fn open_palette(&mut self) -> Result<(), JsValue> {
+        self.palette_index = 0;
+        self.env.palette_input().set_value("");
+        self.render_palette()?;
+        self.env.palette().remove_attribute("hidden")?;
+        self.env.palette_input().focus()
+    }
+
+    fn render_palette(&self) -> Result<(), JsValue> {
+        let matches = palette::search(&self.env.palette_input().value());
+        palette::render(self.env.palette_list(), &matches, self.palette_index, &self.keymap.borrow())
+    }
+
+    fn render(&self) -> Result<(), JsValue> {
+        let modified = self.current().map(Document::dirty).unwrap_or(false);
+        if modified {
+            self.env.app_modified().remove_attribute("hidden")?;
+        } else {
+            self.env.app_modified().set_attribute("hidden", "")?;
+        }
+        let read_only = self.current().map(Document::read_only).unwrap_or(false);
+        self.env.app_read_only().set_text_content(Some(if read_only { "lock" } else { "lock_open" }));
+        if let Some(doc) = self.current() {
+            self.env.app_use_header().set_checked(doc.has_header());
+        }
+        let counts = self.current()
+            .filter(|doc| !doc.grid().filters().borrow().is_empty())
+            .map(|doc| doc.grid().counts());
+        let counts = counts.map(|(shown, total)| format!("{} of {} rows", shown, total));
+        self.env.quick_filter_count().set_text_content(counts.as_deref());
+        let editable = self.current().filter(|doc| !doc.read_only());
+        self.env.app_undo().set_disabled(!editable.map(Document::can_undo).unwrap_or(false));
+        self.env.app_redo().set_disabled(!editable.map(Document::can_redo).unwrap_or(false));
+        status::render(self.env.status(), self.current())?;
+        tabs::render(self.env.tabs(), &self.documents, self.active)
+    }
+}
+
+fn parse(text: &str, header: bool, delimiter: char) -> Result<Csv, JsValue> {
+    Csv::parse_with_delimiter(text, header, delimiter)
+        .map_err(|e| format!("failed to parse csv {}", e).into())
+}
+
+fn decode(bytes: &[u8]) -> (String, Option<EncodingRef>) {
+    use encoding::label::encoding_from_whatwg_label;
+
+    let (encoding, _, _) = chardet::detect(bytes);
     let mut using_coder = encoding_from_whatwg_label(chardet::charset2encoding(&encoding));
     let text = if let Some(coder) = using_coder {
-        coder.decode(&bytes, encoding::DecoderTrap::Replace).ok()
+        coder.decode(bytes, encoding::DecoderTrap::Replace).ok()
     } else {
         None
     };
@@ -47,49 +267,226 @@ async fn load_csv(file: &gloo::file::File, state: &mut State) -> Result<(), JsVa
         text
     } else {
         using_coder = None;
-        String::from_utf8_lossy(&bytes).to_string()
+        String::from_utf8_lossy(bytes).to_string()
     };
+    (text, using_coder)
+}
 
-    let State { env, ref mut grid, ref mut coder } = state;
+async fn load_csv(
+    file: &gloo::file::File,
+    handle: Option<FileSystemFileHandle>,
+    state: &mut State) -> Result<(), JsValue> {
 
-    let root = env.root();
-    let document = root.owner_document().ok_or("no owner document found")?;
-    let div = document.create_element("div")?;
-    if let Some(old) = root.first_element_child() {
-        old.replace_with_with_node_1(&div)?;
-    } else {
-        root.append_child(&div)?;
-    };
+    use gloo::file::futures::read_as_bytes;
+
+    // only a document of the same file is reloaded, files without a handle open in a new tab
+    let mut existing = None;
+    if let Some(handle) = &handle {
+        for (index, doc) in state.documents.iter().enumerate() {
+            if let Some(other) = doc.handle() {
+                if file::same_entry(handle, other).await {
+                    existing = Some(index);
+                    break
+                }
+            }
+        }
+    }
+    if let Some(index) = existing {
+        let doc = &state.documents[index];
+        if doc.dirty() && !confirm(&format!("{} has unsaved changes. Discard them and reload?", doc.name())) {
+            return state.activate(index)
+        }
+    }
+
+    let bytes = read_as_bytes(file).await.map_err(|e| format!("failed to read file {}", e))?;
+    let (text, coder) = decode(&bytes);
+
+    let csv = parse(&text, state.env.app_use_header().checked(), ',')?;
+    let entry = recent::Entry::new(file.name(), file.size() as f64, handle.clone());
+    let index = state.open(file.name(), csv, coder, handle, existing)?;
+    state.activate(index)?;
+    state.update_dirty().await?;
+
+    if let Some(db) = &state.db {
+        // the file is open, failing to remember it is not worth an error
+        if let Err(err) = recent::add(db, entry).await {
+            log::error!("{:?}", err);
+        }
+    }
+    state.render_recent().await?;
+
+    state.env.mdc_drawer().set_open(false);
+    Ok(())
+}
+
+/// Opens files from the file picker, which keeps their handles, or else from the file input.
+/// The picker is called directly since clicking the input cannot be cancelled from an async handler.
+async fn open_files(state: &mut State) -> Result<(), JsValue> {
+    if !state.env.file_system_access() {
+        state.env.input_file().click();
+        return Ok(())
+    }
+    for (file, handle) in file::pick_open_files().await? {
+        load_csv(&file, Some(handle), state).await?;
+    }
+    Ok(())
+}
+
+async fn open_url(url: &str, state: &mut State) -> Result<(), JsValue> {
+    let bytes = file::fetch(url).await?;
+    let (text, coder) = decode(&bytes);
+
+    let csv = parse(&text, state.env.app_use_header().checked(), ',')?;
+    let index = state.open(url.to_string(), csv, coder, None, None)?;
+    state.documents[index].set_read_only(true);
+    state.activate(index)?;
+    state.update_dirty().await?;
+
+    state.env.mdc_drawer().set_open(false);
+    Ok(())
+}
+
+async fn paste_as_new(state: &mut State) -> Result<(), JsValue> {
+    let text = JsFuture::from(clipboard::read_text()?).await?
+        .as_string().ok_or("clipboard does not contain text")?;
+    if text.is_empty() {
+        return Err("clipboard is empty".into())
+    }
 
-    *grid = Some(grid::Grid::new(div, file.name(), &text, env.app_use_header().checked())?);
-    *coder = using_coder;
+    let delimiter = Csv::sniff_delimiter(&text);
+    let csv = parse(&text, state.env.app_use_header().checked(), delimiter)?;
+    state.untitled += 1;
+    let extension = if delimiter == '\t' { "tsv" } else { "csv" };
+    let name = format!("clipboard-{}.{}", state.untitled, extension);
 
-    env.mdc_drawer().set_open(false);
-    env.app_save().set_disabled(false);
+    let index = state.open(name, csv, None, None, None)?;
+    state.documents[index].mark_unsaved();
+    state.activate(index)?;
+    state.update_dirty().await?;
+
+    state.env.mdc_drawer().set_open(false);
     Ok(())
 }
 
+async fn restore_session(state: &mut State) -> Result<(), JsValue> {
+    use encoding::label::encoding_from_whatwg_label;
+
+    let db = if let Some(db) = &state.db {
+        db.clone()
+    } else {
+        return Ok(())
+    };
+    let snapshots = session::load(&db).await?;
+    if snapshots.is_empty() {
+        return Ok(())
+    }
+
+    let message = format!("Restore {} unsaved document(s) from the last session?", snapshots.len());
+    if confirm(&message) {
+        for snapshot in snapshots {
+            let coder = snapshot.encoding.as_deref().and_then(encoding_from_whatwg_label);
+            let csv = parse(&snapshot.text, snapshot.header, snapshot.delimiter)?;
+            let index = state.open(snapshot.name, csv, coder, None, None)?;
+            state.documents[index].mark_unsaved();
+            state.activate(index)?;
+        }
+        state.update_dirty().await?;
+    }
+    session::clear(&db).await
+}
+
 #[derive(Debug, Clone)]
 enum EventType {
+    Open,
+    OpenUrl,
+    PasteNew,
     FileChanged,
     Save,
+    SaveAs,
+    ReadOnly,
+    UseHeader,
+    Undo,
+    Redo,
     ContextMenu,
     MenuSelected,
+    ColumnMenuSelected,
+    FindToggle,
+    Find,
+    FindChanged,
+    Filter,
+    GoTo,
+    KeyDown,
+    KeymapToggle,
+    Keymap,
+    PaletteInput,
+    Palette,
+    QuickFilterInput,
+    QuickFilter,
     AppBarNav,
     DragOver,
     Drop,
+    TabSelected,
+    Changed,
+    SelectionChanged,
+    LayoutChanged,
+    Autosave,
+    RecentSelected,
+    DatabaseOpened,
 }
 
 impl EventType {
     async fn handle(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
         match self {
+            Self::Open => self.handle_open(event, state).await,
+            Self::OpenUrl => self.handle_open_url(event, state).await,
+            Self::PasteNew => paste_as_new(state).await,
             Self::FileChanged => self.handle_file_changed(event, state).await,
-            Self::Save => self.handle_save(event, state).await,
+            Self::Save | Self::SaveAs => self.handle_save(event, state).await,
+            Self::ReadOnly => self.handle_read_only(event, state).await,
+            Self::UseHeader => self.handle_use_header(event, state).await,
+            Self::Undo | Self::Redo => self.handle_undo(event, state).await,
             Self::ContextMenu => self.handle_context_menu(event, state).await,
             Self::MenuSelected => self.handle_menu_selected(event, state).await,
+            Self::ColumnMenuSelected => self.handle_column_menu_selected(event, state).await,
+            Self::FindToggle => self.handle_find_toggle(event, state).await,
+            Self::Find => self.handle_find(event, state).await,
+            Self::Filter => self.handle_filter(event, state).await,
+            Self::GoTo => self.handle_goto(event, state).await,
+            Self::KeyDown => self.handle_key_down(event, state).await,
+            Self::KeymapToggle => self.handle_keymap_toggle(event, state).await,
+            Self::Keymap => self.handle_keymap(event, state).await,
+            Self::PaletteInput => {
+                state.palette_index = 0;
+                state.render_palette()
+            }
+            Self::Palette => self.handle_palette(event, state).await,
+            Self::QuickFilterInput => {
+                let input = state.env.quick_filter().clone();
+                state.quick_filter_timer = Some(Timeout::new(QUICK_FILTER_DELAY, move || {
+                    if let Ok(event) = Event::new(QUICK_FILTER) {
+                        input.dispatch_event(&event).ok();
+                    }
+                }));
+                Ok(())
+            }
+            Self::QuickFilter => self.handle_quick_filter(event, state).await,
+            Self::FindChanged => {
+                state.find_selection = None;
+                Ok(())
+            }
             Self::AppBarNav => self.handle_app_bar_nav(event, state).await,
             Self::DragOver => self.handle_drag_over(event, state).await,
             Self::Drop => self.handle_drop(event, state).await,
+            Self::TabSelected => self.handle_tab_selected(event, state).await,
+            Self::Changed => state.update_dirty().await,
+            Self::SelectionChanged => state.render(),
+            Self::LayoutChanged => match state.current() {
+                Some(doc) => doc.grid().save_layout(),
+                None => Ok(()),
+            },
+            Self::Autosave => state.autosave().await,
+            Self::DatabaseOpened => self.handle_database_opened(event, state).await,
+            Self::RecentSelected => self.handle_recent_selected(event, state).await,
         }
     }
 
@@ -111,9 +508,24 @@ impl EventType {
             return Ok(())
         };
 
-        if let Some(file) = file_list.iter().next() {
-            load_csv(file, state).await?;
-        };
+        for file in file_list.iter() {
+            load_csv(file, None, state).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_open(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        if !state.env.file_system_access() {
+            return Ok(())
+        }
+        event.prevent_default();
+        open_files(state).await
+    }
+
+    async fn handle_open_url(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
+        if let Some(url) = prompt("URL", None).filter(|url| !url.is_empty()) {
+            open_url(&url, state).await?;
+        }
         Ok(())
     }
 
@@ -128,81 +540,472 @@ impl EventType {
             return Ok(())
         };
 
-        if let Some(file) = file_list.iter().next() {
-            load_csv(file, state).await?;
-        };
+        for file in file_list.iter() {
+            load_csv(file, None, state).await?;
+        }
         Ok(())
     }
 
+    async fn handle_database_opened(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let event = event.dyn_ref::<CustomEvent>().ok_or("event type mismatch")?;
+        state.db = Some(event.detail().dyn_into::<IdbDatabase>()?);
+        if let Err(err) = restore_session(state).await {
+            log::error!("{:?}", err);
+        }
+        state.render_recent().await
+    }
+
+    async fn handle_recent_selected(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let id = event.target()
+            .and_then(|t| t.dyn_into::<Element>().ok())
+            .and_then(|t| t.closest("[data-id]").ok().flatten())
+            .and_then(|item| item.get_attribute("data-id"));
+        let (id, db) = if let (Some(id), Some(db)) = (id, &state.db) {
+            (id, db)
+        } else {
+            return Ok(())
+        };
+
+        let entry = recent::load(db).await?.into_iter().find(|e| e.id == id);
+        match entry.and_then(|e| e.handle) {
+            Some(handle) => {
+                let file = file::open_handle(&handle).await?;
+                load_csv(&file, Some(handle), state).await
+            }
+            None => open_files(state).await,
+        }
+    }
+
+    async fn handle_tab_selected(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let target = if let Some(target) = event.target().and_then(|t| t.dyn_into::<Element>().ok()) {
+            target
+        } else {
+            return Ok(())
+        };
+        let index = target.closest("[data-index]")?
+            .and_then(|tab| tab.get_attribute("data-index"))
+            .and_then(|index| index.parse::<usize>().ok());
+        let index = if let Some(index) = index {
+            index
+        } else {
+            return Ok(())
+        };
+
+        if target.closest("[data-action='close']")?.is_some() {
+            if let Some(doc) = state.documents.get(index) {
+                if doc.dirty() && !confirm(&format!("{} has unsaved changes. Close it anyway?", doc.name())) {
+                    return Ok(())
+                }
+            }
+            state.close(index)?;
+            state.update_dirty().await
+        } else if state.active != Some(index) {
+            state.activate(index)
+        } else {
+            Ok(())
+        }
+    }
+
     async fn handle_save(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
-        let State { grid, env, coder } = state;
-        if let Some(grid) = &grid {
-            let csv = grid.csv();
-            let csv = csv.lock().await;
-            let csv_content = format!("{}", &*csv);
-            let content = if let Some(coder) = coder {
-                coder.encode(&csv_content, encoding::EncoderTrap::Replace).ok()
+        let doc = if let Some(doc) = state.current() {
+            doc
+        } else {
+            return Ok(())
+        };
+        {
+            let csv = doc.grid().csv();
+            let mut csv = csv.lock().await;
+            doc.grid().apply_save_order(&mut csv)?;
+        }
+        let content = doc.to_bytes().await;
+
+        let handle = match doc.handle() {
+            Some(handle) if matches!(self, Self::Save) => Some(handle.clone()),
+            _ if state.env.file_system_access() => {
+                if let Some(handle) = file::pick_save_file(doc.name()).await? {
+                    Some(handle)
+                } else {
+                    return Ok(())
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(handle) = handle {
+            file::write(&handle, &content).await?;
+            if let Some(doc) = state.current_mut() {
+                doc.set_handle(handle);
+            }
+        } else {
+            let name = if matches!(self, Self::SaveAs) {
+                if let Some(name) = prompt("Save as", Some(doc.name())) {
+                    name
+                } else {
+                    return Ok(())
+                }
             } else {
-                None
+                doc.name().to_string()
             };
-            let content = content.unwrap_or_else(|| csv_content.as_bytes().to_vec());
-            let parts = Array::of1(Uint8Array::from(content.as_ref()).buffer().as_ref());
-            let blob = File::new_with_buffer_source_sequence_and_options(
-                &parts,
-                &grid.name(),
-                FilePropertyBag::new().type_("text/csv"))?;
-            let url = Url::create_object_url_with_blob(&blob)?;
-            env.location().assign(&url)?;
-            Url::revoke_object_url(&url)?;
+            file::download(state.env.location(), &name, &content)?;
+            if let Some(doc) = state.current_mut() {
+                doc.set_name(name);
+            }
         }
-        Ok(())
+
+        if let Some(doc) = state.current_mut() {
+            doc.mark_saved().await;
+        }
+        state.update_dirty().await
+    }
+
+    async fn handle_read_only(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
+        if let Some(doc) = state.current_mut() {
+            let read_only = !doc.read_only();
+            doc.set_read_only(read_only);
+        }
+        state.render()
+    }
+
+    async fn handle_use_header(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let use_header = state.env.app_use_header().checked();
+        if let Some(doc) = state.current().filter(|doc| !doc.read_only()) {
+            let csv = doc.grid().csv();
+            let mut csv = csv.lock().await;
+            if use_header != csv.has_header() {
+                if use_header {
+                    csv.promote_to_header(0);
+                } else {
+                    csv.demote_header();
+                }
+                doc.grid().refresh(&csv)?;
+            }
+        }
+        state.update_dirty().await
+    }
+
+    async fn handle_undo(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
+        if let Some(doc) = state.current().filter(|doc| !doc.read_only()) {
+            let csv = doc.grid().csv();
+            let mut csv = csv.lock().await;
+            let done = match self {
+                Self::Undo => csv.undo(),
+                _ => csv.redo(),
+            };
+            let changes = csv.take_column_changes();
+            if !changes.is_empty() {
+                doc.grid().follow_columns(&changes);
+                doc.grid().save_layout()?;
+            }
+            if done {
+                doc.grid().refresh(&csv)?;
+            }
+        }
+        state.update_dirty().await
     }
 
     async fn handle_context_menu(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
-        let State { grid, env, .. } = state;
-        if grid.is_some() {
-            let event = event.dyn_ref::<MouseEvent>().ok_or("event type mismatch")?;
-            event.prevent_default();
-            env.mdc_menu().set_absolute_position(event.client_x(), event.client_y())?;
-            env.mdc_menu().set_open(true);
+        let column = if let Some(doc) = state.current() {
+            let csv = doc.grid().csv();
+            let csv = csv.lock().await;
+            doc.grid().take_context_column(&csv)
+        } else {
+            return Ok(())
+        };
+        state.menu_column = column;
+
+        let event = event.dyn_ref::<MouseEvent>().ok_or("event type mismatch")?;
+        event.prevent_default();
+        if let Some(doc) = state.current().filter(|_| column.is_some()) {
+            let label = if doc.grid().columns().borrow().apply_order() {
+                "Save Columns in File Order"
+            } else {
+                "Save Columns in Display Order"
+            };
+            if let Some(item) = state.env.column_menu().query_selector(".app-apply-order")? {
+                item.set_text_content(Some(label));
+            }
         }
+        let menu = if column.is_some() { state.env.mdc_column_menu() } else { state.env.mdc_menu() };
+        menu.set_absolute_position(event.client_x(), event.client_y())?;
+        menu.set_open(true);
         Ok(())
     }
 
     async fn handle_menu_selected(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
-        let State { grid, .. } = state;
-        let event = event.dyn_ref::<CustomEvent>().ok_or("event type mismatch")?;
-        let detail = event.detail();
-        #[allow(unused_unsafe)]
-        let item = unsafe {
-            Reflect::get(&detail, &"item".into())?
+        match menu_action(event)? {
+            Some(action) => row_action(&action, state).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn handle_key_down(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let event = event.dyn_ref::<KeyboardEvent>().ok_or("event type mismatch")?;
+        let keymap_open = !state.env.keymap().has_attribute("hidden");
+        if let Some(input) = keymap::recording(event) {
+            let action = input.get_attribute("data-action").ok_or("no action")?;
+            let key = match keymap::combo(event) {
+                Some(key) if key == "Escape" => return state.env.keymap().set_attribute("hidden", ""),
+                Some(key) if key == "Backspace" || key == "Delete" => None,
+                Some(key) => Some(key),
+                None => return Ok(()),
+            };
+            let mut keymap = state.keymap.borrow_mut();
+            keymap.set(&action, key);
+            keymap.save()?;
+            keymap::render(state.env.keymap_table(), &keymap)?;
+            let selector = format!("[data-action='{}']", action);
+            if let Some(input) = state.env.keymap_table().query_selector(&selector)? {
+                input.dyn_into::<HtmlElement>()?.focus()?;
+            }
+            return Ok(())
+        }
+        if event.target().as_ref() == Some(state.env.palette_input().as_ref()) {
+            let matches = palette::search(&state.env.palette_input().value());
+            match event.key().as_str() {
+                "ArrowDown" => {
+                    state.palette_index = (state.palette_index + 1).min(matches.len().saturating_sub(1));
+                    return state.render_palette()
+                }
+                "ArrowUp" => {
+                    state.palette_index = state.palette_index.saturating_sub(1);
+                    return state.render_palette()
+                }
+                "Enter" => {
+                    state.env.palette().set_attribute("hidden", "")?;
+                    return match matches.get(state.palette_index) {
+                        Some(action) => run_action(action.name, event, state).await,
+                        None => Ok(()),
+                    }
+                }
+                "Escape" => return state.env.palette().set_attribute("hidden", ""),
+                _ => {}
+            }
+        }
+        if event.target().as_ref() == Some(state.env.goto_text().as_ref()) && event.key() == "Escape" {
+            return state.env.goto().set_attribute("hidden", "")
+        }
+        if keymap_open && event.key() == "Escape" {
+            return state.env.keymap().set_attribute("hidden", "")
+        }
+        let action = state.keymap.borrow().action(event);
+        match action {
+            Some(action) => run_action(action, event, state).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn handle_keymap_toggle(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let overlay = state.env.keymap();
+        if overlay.has_attribute("hidden") {
+            keymap::render(state.env.keymap_table(), &state.keymap.borrow())?;
+            overlay.remove_attribute("hidden")
+        } else {
+            overlay.set_attribute("hidden", "")
+        }
+    }
+
+    async fn handle_keymap(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let target = if let Some(target) = event.target().and_then(|t| t.dyn_into::<Element>().ok()) {
+            target
+        } else {
+            return Ok(())
         };
-        let item = item.dyn_into::<HtmlElement>()?;
-        if let (Some(grid), Some(action)) = (&grid, &item.dataset().get("action")) {
-            let csv = grid.csv();
-            let mut csv = csv.lock().await;
-            let grid = grid.grid();
+        let action = target.closest("button[data-action]")?.and_then(|e| e.get_attribute("data-action"));
+        match action.as_deref() {
+            Some("reset") => {
+                let mut keymap = state.keymap.borrow_mut();
+                keymap.reset();
+                keymap.save()?;
+                keymap::render(state.env.keymap_table(), &keymap)
+            }
+            Some("close") => state.env.keymap().set_attribute("hidden", ""),
+            _ if &target == state.env.keymap() => state.env.keymap().set_attribute("hidden", ""),
+            _ => Ok(()),
+        }
+    }
+
+    async fn handle_palette(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let target = if let Some(target) = event.target().and_then(|t| t.dyn_into::<Element>().ok()) {
+            target
+        } else {
+            return Ok(())
+        };
+        let action = target.closest("[data-action]")?.and_then(|e| e.get_attribute("data-action"));
+        match action.as_deref().and_then(keymap::find) {
+            Some(action) => {
+                state.env.palette().set_attribute("hidden", "")?;
+                run_action(action.name, event, state).await
+            }
+            None if &target == state.env.palette() => state.env.palette().set_attribute("hidden", ""),
+            None => Ok(()),
+        }
+    }
+
+    async fn handle_column_menu_selected(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let (action, col) = match (menu_action(event)?, state.menu_column) {
+            (Some(action), Some(col)) => (action, col),
+            _ => return Ok(())
+        };
+        column_action(&action, col, state).await
+    }
 
-            let selection = grid.selection()?;
-            #[allow(unused_unsafe)]
-            let row = unsafe {
-                let select = Reflect::get(&selection, &"select".into())?;
-                Reflect::get(&select, &"row".into())?
+    async fn handle_quick_filter(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
+        state.quick_filter_timer = None;
+        if let Some(doc) = state.current() {
+            doc.grid().filters().borrow_mut().set_quick(&state.env.quick_filter().value());
+            let csv = doc.grid().csv();
+            let csv = csv.lock().await;
+            doc.grid().refresh(&csv)?;
+        }
+        state.render()
+    }
+
+    async fn handle_filter(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let action = if event.type_() == "submit" {
+            event.prevent_default();
+            Some("apply".to_string())
+        } else {
+            event.target().and_then(|t| t.dyn_into::<Element>().ok())
+                .and_then(|t| t.closest("[data-action]").ok().flatten())
+                .and_then(|e| e.get_attribute("data-action"))
+        };
+        let (action, col, doc) = match (action, state.filter_column, state.current()) {
+            (Some(action), Some(col), Some(doc)) => (action, col, doc),
+            _ => return Ok(()),
+        };
+        match action.as_ref() {
+            "apply" => {
+                let condition = filter::Condition::new(
+                    &state.env.filter_kind().value(),
+                    &state.env.filter_value().value(),
+                    &state.env.filter_max().value())?;
+                doc.grid().filters().borrow_mut().set(col, condition);
+            }
+            "clear_filter" => doc.grid().filters().borrow_mut().remove(col),
+            "close" => {}
+            _ => return Ok(()),
+        }
+        let csv = doc.grid().csv();
+        let csv = csv.lock().await;
+        doc.grid().refresh(&csv)?;
+        state.env.filter().set_attribute("hidden", "")
+    }
+
+    async fn handle_goto(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        if event.type_() == "submit" {
+            event.prevent_default();
+        } else {
+            let close = event.target().and_then(|t| t.dyn_into::<Element>().ok())
+                .and_then(|t| t.closest("[data-action='close']").ok().flatten());
+            if close.is_some() {
+                state.env.goto().set_attribute("hidden", "")?;
+            }
+            return Ok(())
+        }
+        if let Some(doc) = state.current() {
+            let csv = doc.grid().csv();
+            let csv = csv.lock().await;
+            let (row, col) = goto::parse(&csv, &state.env.goto_text().value())?;
+            let col = match col {
+                Some(col) => col,
+                None => match doc.grid().cursor(&csv)? {
+                    Some((_, col)) => col,
+                    None => doc.grid().visible_cols(&csv).first().copied().ok_or("all columns are hidden")?,
+                },
             };
-            match (action.as_ref(), row.as_f64().map(|f| f as usize)) {
-                ("add_before", Some(row)) if (1..=csv.rows()).contains(&row) => csv.insert_row(row - 1),
-                ("add_after", Some(row)) if (0..=csv.rows()).contains(&row) => csv.insert_row(row - 0),
-                ("remove", Some(row)) if (1..=csv.rows()).contains(&row) => csv.remove_row(row - 1),
-                _ => return Ok(()),
-            }
-            let data_source = grid.data_source()?;
-            data_source.set_length(csv.rows())?;
-            data_source.clear_cache()?;
-            grid.invalidate()?;
+            doc.grid().go_to(&csv, row, col)?;
+        }
+        state.env.goto().set_attribute("hidden", "")?;
+        state.render()
+    }
+
+    async fn handle_find_toggle(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let find = state.env.find();
+        if find.has_attribute("hidden") {
+            state.find_selection = None;
+            state.env.find_status().set_text_content(None);
+            find.remove_attribute("hidden")?;
+            state.env.find_text().focus()?;
+            state.env.find_text().select();
+        } else {
+            find.set_attribute("hidden", "")?;
         }
         Ok(())
     }
 
+    async fn handle_find(&self, event: &Event, state: &mut State) -> Result<(), JsValue> {
+        let action = if event.type_() == "submit" {
+            event.prevent_default();
+            "find_next".to_string()
+        } else {
+            let target = event.target().and_then(|t| t.dyn_into::<Element>().ok());
+            let action = target.and_then(|t| t.closest("[data-action]").ok().flatten())
+                .and_then(|e| e.get_attribute("data-action"));
+            if let Some(action) = action {
+                action
+            } else {
+                return Ok(())
+            }
+        };
+        if action == "close" {
+            return state.env.find().set_attribute("hidden", "");
+        }
+
+        let State { env, documents, active, find_selection, .. } = state;
+        let pattern = env.find_text().value();
+        let doc = match active.and_then(|index| documents.get(index)) {
+            Some(doc) if !pattern.is_empty() => doc,
+            _ => return Ok(()),
+        };
+        let finder = find::finder(&pattern, &env.find_mode().value())?;
+        let replacement = env.replace_text().value();
+        let scope = env.find_scope().value();
+
+        let csv = doc.grid().csv();
+        let mut csv = csv.lock().await;
+        if scope == "selection" && find_selection.is_none() {
+            *find_selection = Some(doc.grid().selection(&csv)?);
+        }
+        let (rows, cols) = find::scope(doc.grid(), &csv, &scope, find_selection.as_ref())?;
+
+        let status = match action.as_ref() {
+            "find_next" | "find_prev" | "replace" => {
+                let cursor = doc.grid().cursor(&csv)?;
+                if action == "replace" && !doc.read_only() {
+                    if let Some((row, col)) = cursor.filter(|&(row, col)| {
+                        rows.contains(&row) && cols.contains(&col) &&
+                            csv.val(row, col).map(|val| finder.is_match(val)).unwrap_or(false)
+                    }) {
+                        let val = csv.val(row, col).map(|val| finder.replace(val, &replacement).into_owned());
+                        if let Some(val) = val {
+                            csv.set_val(row, col, val);
+                            doc.grid().refresh(&csv)?;
+                        }
+                    }
+                }
+                let matches = csv.find(&finder, &rows, &cols);
+                let visible = doc.grid().visible_cols(&csv);
+                match find::next(&matches, cursor, &visible, action != "find_prev") {
+                    Some((row, col)) => {
+                        doc.grid().select_cell(&csv, row, col)?;
+                        let index = matches.iter().position(|&m| m == (row, col)).unwrap_or(0);
+                        format!("{} of {}", index + 1, matches.len())
+                    }
+                    None => "No results".to_string(),
+                }
+            }
+            "replace_all" if !doc.read_only() => {
+                let count = csv.replace_all(&finder, &replacement, &rows, &cols);
+                doc.grid().refresh(&csv)?;
+                format!("{} replaced", count)
+            }
+            _ => return Ok(()),
+        };
+        drop(csv);
+        state.env.find_status().set_text_content(Some(&status));
+        state.update_dirty().await
+    }
+
     async fn handle_app_bar_nav(&self, _event: &Event, state: &mut State) -> Result<(), JsValue> {
         let drawer = state.env.mdc_drawer();
         drawer.set_open(!drawer.open());
@@ -210,33 +1013,350 @@ impl EventType {
     }
 }
 
+/// Runs a copy format or row operation of the row menu against the current selection.
+async fn row_action(action: &str, state: &mut State) -> Result<(), JsValue> {
+    if let Some(format) = copy::Format::from_action(action) {
+        if let Some(doc) = state.current() {
+            let text = {
+                let csv = doc.grid().csv();
+                let csv = csv.lock().await;
+                let selection = doc.grid().selection(&csv)?;
+                copy::serialize(&csv, &selection, format)
+            };
+            JsFuture::from(clipboard::write_text(&text)?).await?;
+        }
+        return Ok(())
+    }
+
+    if action == "edit_text" {
+        if let Some(doc) = state.current() {
+            let csv = doc.grid().csv();
+            let csv = csv.lock().await;
+            doc.grid().edit_text(&csv)?;
+        }
+        return Ok(())
+    }
+
+    if let Some(doc) = state.current().filter(|doc| !doc.read_only()) {
+        let csv = doc.grid().csv();
+        let mut csv = csv.lock().await;
+
+        let selection = doc.grid().selection(&csv)?;
+        let rows = selection.rows.iter().copied().filter(|&row| row < csv.rows()).collect::<Vec<_>>();
+        let count = rows.len().max(1);
+        let blocks = selection.row_blocks().into_iter().filter(|block| block.end <= csv.rows()).collect::<Vec<_>>();
+        // blocks are handled from the last one where an edit shifts the rows after a block
+        match action {
+            "add_before" if !rows.is_empty() => csv.insert_rows(rows[0], count),
+            "add_after" => csv.insert_rows(rows.last().map(|row| row + 1).unwrap_or(0), count),
+            "remove" if !rows.is_empty() => csv.remove_rows(&rows),
+            "duplicate" if !blocks.is_empty() => csv.transaction(|csv| {
+                for block in blocks.into_iter().rev() {
+                    csv.duplicate_rows(block);
+                }
+            }),
+            "move_up" if blocks.first().map(|block| block.start > 0).unwrap_or(false) => csv.transaction(|csv| {
+                for block in blocks {
+                    let index = block.start - 1;
+                    csv.move_rows(block, index);
+                }
+            }),
+            "move_down" if blocks.last().map(|block| block.end < csv.rows()).unwrap_or(false) => csv.transaction(|csv| {
+                for block in blocks.into_iter().rev() {
+                    let index = block.start + 1;
+                    csv.move_rows(block, index);
+                }
+            }),
+            "promote_header" if !rows.is_empty() => csv.promote_to_header(rows[0]),
+            "demote_header" if csv.has_header() => {
+                csv.demote_header();
+            }
+            _ => return Ok(()),
+        }
+        doc.grid().refresh(&csv)?;
+    }
+    state.update_dirty().await
+}
+
+/// Runs an operation of the column menu on csv column `col`.
+async fn column_action(action: &str, col: usize, state: &mut State) -> Result<(), JsValue> {
+    if action == "filter" {
+        let caption = match state.current() {
+            Some(doc) => {
+                let csv = doc.grid().csv();
+                let csv = csv.lock().await;
+                csv.header(col).map(ToString::to_string).unwrap_or_else(|| col.to_string())
+            }
+            None => return Ok(()),
+        };
+        state.filter_column = Some(col);
+        let env = &state.env;
+        env.filter_column().set_text_content(Some(&caption));
+        env.filter().remove_attribute("hidden")?;
+        return env.filter_value().focus()
+    }
+    let doc = if let Some(doc) = state.current() {
+        doc
+    } else {
+        return Ok(())
+    };
+    let csv = doc.grid().csv();
+    let mut csv = csv.lock().await;
+    let read_only = doc.read_only();
+
+    {
+        let mut columns = doc.grid().columns().borrow_mut();
+        let mut filters = doc.grid().filters().borrow_mut();
+        match action {
+            "insert_left" if !read_only => {
+                csv.insert_col(col);
+                columns.insert(col);
+                filters.insert_col(col);
+            }
+            "insert_right" if !read_only => {
+                csv.insert_col(col + 1);
+                columns.insert(col + 1);
+                filters.insert_col(col + 1);
+            }
+            "remove_column" if !read_only => {
+                csv.remove_col(col);
+                columns.remove(col);
+                filters.remove_col(col);
+            }
+            "rename" if !read_only => {
+                let current = csv.header(col).unwrap_or("").to_string();
+                if let Some(name) = prompt("Header name", Some(&current)) {
+                    csv.set_header(col, name);
+                }
+            }
+            "sort_asc" | "sort_desc" if !read_only => {
+                let kind = columns.get(col).kind.unwrap_or_else(|| column::ColumnType::infer(csv.cols(col)));
+                csv.sort_by_col(col, action == "sort_asc", kind == column::ColumnType::Number);
+            }
+            "auto_fit" => columns.get_mut(col).width = None,
+            "auto_fit_all" => columns.fit_all(),
+            "clear_filter" => filters.remove(col),
+            "clear_filters" => filters.clear(),
+            "hide" => columns.get_mut(col).hidden = true,
+            "show_all" => columns.show_all(),
+            "move_left" | "move_right" => {
+                let visible = columns.visible(csv.max_cols());
+                let position = visible.iter().position(|&c| c == col);
+                let target = match (action, position) {
+                    ("move_left", Some(position)) if position > 0 => visible.get(position - 1),
+                    ("move_right", Some(position)) => visible.get(position + 1),
+                    _ => None,
+                };
+                match target {
+                    Some(&target) => columns.move_col(col, target, csv.max_cols()),
+                    None => return Ok(()),
+                }
+            }
+            "freeze" => {
+                let visible = columns.visible(csv.max_cols());
+                match visible.iter().position(|&c| c == col) {
+                    Some(position) => columns.set_frozen(position + 1),
+                    None => return Ok(()),
+                }
+            }
+            "unfreeze" => columns.set_frozen(0),
+            "reset_order" => columns.reset_order(),
+            "apply_order" => {
+                let apply = !columns.apply_order();
+                columns.set_apply_order(apply);
+            }
+            action => match column::ColumnType::from_action(action) {
+                Some(kind) => columns.get_mut(col).kind = Some(kind),
+                None => return Ok(()),
+            }
+        }
+    }
+    doc.grid().refresh(&csv)?;
+    drop(csv);
+    let layout = [
+        "insert_left", "insert_right", "remove_column", "hide", "show_all",
+        "freeze", "unfreeze", "move_left", "move_right", "reset_order", "apply_order",
+    ];
+    if layout.contains(&action) {
+        doc.grid().save_layout()?;
+    }
+    state.update_dirty().await
+}
+
+/// Runs a named action of the keymap or the command palette.
+async fn run_action(action: &str, event: &Event, state: &mut State) -> Result<(), JsValue> {
+    use EventType::*;
+
+    match action {
+        "open" => open_files(state).await,
+        "open_url" => OpenUrl.handle_open_url(event, state).await,
+        "paste_new" => paste_as_new(state).await,
+        "save" => Save.handle_save(event, state).await,
+        "save_as" => SaveAs.handle_save(event, state).await,
+        "read_only" => ReadOnly.handle_read_only(event, state).await,
+        "find" if !state.env.find().has_attribute("hidden") => {
+            state.env.find_text().focus()?;
+            state.env.find_text().select();
+            Ok(())
+        }
+        "find" => FindToggle.handle_find_toggle(event, state).await,
+        "goto" => {
+            state.env.goto().remove_attribute("hidden")?;
+            state.env.goto_text().focus()?;
+            state.env.goto_text().select();
+            Ok(())
+        }
+        "quick_filter" => {
+            state.env.quick_filter().focus()?;
+            state.env.quick_filter().select();
+            Ok(())
+        }
+        "undo" => Undo.handle_undo(event, state).await,
+        "redo" => Redo.handle_undo(event, state).await,
+        "use_header" => {
+            let input = state.env.app_use_header();
+            input.set_checked(!input.checked());
+            UseHeader.handle_use_header(event, state).await
+        }
+        "drawer" => AppBarNav.handle_app_bar_nav(event, state).await,
+        "palette" => state.open_palette(),
+        "keymap" => KeymapToggle.handle_keymap_toggle(event, state).await,
+        action => match keymap::find(action).map(|action| action.target) {
+            Some(keymap::Target::Column) => {
+                let col = match state.current() {
+                    Some(doc) => {
+                        let csv = doc.grid().csv();
+                        let csv = csv.lock().await;
+                        doc.grid().cursor(&csv)?.map(|(_, col)| col)
+                    }
+                    None => return Ok(()),
+                };
+                let col = col.ok_or("Select a cell in the column first.")?;
+                column_action(action, col, state).await
+            }
+            _ => row_action(action, state).await,
+        }
+    }
+}
+
+fn menu_action(event: &Event) -> Result<Option<String>, JsValue> {
+    let event = event.dyn_ref::<CustomEvent>().ok_or("event type mismatch")?;
+    let detail = event.detail();
+    #[allow(unused_unsafe)]
+    let item = unsafe {
+        Reflect::get(&detail, &"item".into())?
+    };
+    let item = item.dyn_into::<HtmlElement>()?;
+    Ok(item.dataset().get("action"))
+}
+
 async fn async_main() -> Result<(), JsValue> {
     use EventType::*;
 
     let env = Env::initialize()?;
+    env.app_read_only().set_disabled(true);
     env.app_save().set_disabled(true);
+    env.app_save_as().set_disabled(true);
 
-    let mut events = EventStream::new(&[
+    let events = EventStream::new(&[
+        (env.input_file().as_ref(), Open, "click"),
+        (env.app_open_url().as_ref(), OpenUrl, "click"),
+        (env.app_paste_new().as_ref(), PasteNew, "click"),
         (env.input_file().as_ref(), FileChanged, "change"),
         (env.app_save().as_ref(), Save, "click"),
+        (env.app_save_as().as_ref(), SaveAs, "click"),
+        (env.app_read_only().as_ref(), ReadOnly, "click"),
+        (env.app_use_header().as_ref(), UseHeader, "change"),
+        (env.app_find_toggle().as_ref(), FindToggle, "click"),
+        (env.find().as_ref(), Find, "click"),
+        (env.find().as_ref(), Find, "submit"),
+        (env.find().as_ref(), FindChanged, "input"),
+        (env.find().as_ref(), FindChanged, "change"),
+        (env.quick_filter().as_ref(), QuickFilterInput, "input"),
+        (env.quick_filter().as_ref(), QuickFilter, QUICK_FILTER),
+        (env.window().as_ref(), KeyDown, "keydown"),
+        (env.app_keymap_toggle().as_ref(), KeymapToggle, "click"),
+        (env.keymap().as_ref(), Keymap, "click"),
+        (env.palette_input().as_ref(), PaletteInput, "input"),
+        (env.palette().as_ref(), Palette, "click"),
+        (env.goto().as_ref(), GoTo, "click"),
+        (env.goto().as_ref(), GoTo, "submit"),
+        (env.filter().as_ref(), Filter, "click"),
+        (env.filter().as_ref(), Filter, "submit"),
+        (env.app_undo().as_ref(), Undo, "click"),
+        (env.app_redo().as_ref(), Redo, "click"),
         (env.root().as_ref(), ContextMenu, "contextmenu"),
         (env.menu().as_ref(), MenuSelected, "MDCMenu:selected"),
+        (env.column_menu().as_ref(), ColumnMenuSelected, "MDCMenu:selected"),
         (env.header().as_ref(), AppBarNav, "MDCTopAppBar:nav"),
         (env.root().as_ref(), DragOver, "dragover"),
         (env.root().as_ref(), Drop, "drop"),
+        (env.tabs().as_ref(), TabSelected, "click"),
+        (env.root().as_ref(), Changed, grid::CHANGED),
+        (env.root().as_ref(), SelectionChanged, grid::SELECTED),
+        (env.root().as_ref(), LayoutChanged, grid::LAYOUT),
+        (env.recent().as_ref(), RecentSelected, "click"),
     ][..]);
-    let mut state = State { env, grid: None, coder: None };
 
-    while let Some((token, event)) = events.next().await {
-        if let Err(err) = token.handle(&event, &mut state).await {
-            log::error!("{:?}", err);
-            if let Some(err) = err.dyn_ref::<JsError>() {
-                if let Some(err) = JsValue::from(err.message()).as_string() {
-                    state.env.error().set_text_content(Some(&err));
+    let keymap = Rc::new(RefCell::new(keymap::Keymap::load()));
+    let _keymap_guard = {
+        let keymap = keymap.clone();
+        let opt = EventListenerOptions::enable_prevent_default();
+        EventListener::new_with_options(env.window(), "keydown", opt, move |event| {
+            if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+                if keymap.borrow().action(event).is_some() || keymap::recording(event).is_some() {
+                    event.prevent_default();
                 }
-            } else if let Some(err) = err.as_string() {
-                state.env.error().set_text_content(Some(&err));
             }
+        })
+    };
+
+    let unsaved = Rc::new(Cell::new(false));
+    let _unload_guard = {
+        let unsaved = unsaved.clone();
+        let opt = EventListenerOptions::enable_prevent_default();
+        EventListener::new_with_options(env.window(), "beforeunload", opt, move |event| {
+            if unsaved.get() {
+                event.prevent_default();
+                if let Some(event) = event.dyn_ref::<BeforeUnloadEvent>() {
+                    event.set_return_value("");
+                }
+            }
+        })
+    };
+
+    // opened alongside the other events so that a blocked upgrade cannot hold up startup
+    let db_opened = {
+        let window = env.window().clone();
+        stream::once(async move { idb::open(&window).await }).filter_map(|db| async move {
+            let db = match db {
+                Ok(db) => db,
+                Err(err) => {
+                    log::warn!("autosave disabled {:?}", err);
+                    return None
+                }
+            };
+            let init = CustomEventInit::new();
+            init.set_detail(&db);
+            let event = CustomEvent::new_with_event_init_dict("dbopened", &init).ok()?;
+            Some((DatabaseOpened, event.into()))
+        }).boxed_local()
+    };
+
+    let mut state = State { env, documents: vec![], active: None, unsaved, db: None, untitled: 0, menu_column: None, find_selection: None, filter_column: None, quick_filter_timer: None, keymap, palette_index: 0 };
+    let src = UrlSearchParams::new_with_str(&state.env.location().search()?)?.get("src");
+    if let Some(src) = src {
+        if let Err(err) = open_url(&src, &mut state).await {
+            state.report(&err);
+        }
+    }
+
+    let ticks = IntervalStream::new(AUTOSAVE_INTERVAL).map(|_| (Autosave, Event::new("autosave").unwrap()));
+    let mut events = stream::select(stream::select(events, ticks), db_opened);
+
+    while let Some((token, event)) = events.next().await {
+        if let Err(err) = token.handle(&event, &mut state).await {
+            state.report(&err);
         } else {
             state.env.error().set_text_content(None);
         }
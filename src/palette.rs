@@ -0,0 +1,64 @@
+use std::cmp::Reverse;
+
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+use crate::keymap::{Action, Keymap, ACTIONS};
+
+/// Actions whose title contains the characters of `query` in order, best match first.
+pub(crate) fn search(query: &str) -> Vec<&'static Action> {
+    let mut matches = ACTIONS.iter()
+        .filter_map(|action| score(query, action.title).map(|score| (score, action)))
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|(score, _)| Reverse(*score));
+    matches.into_iter().map(|(_, action)| action).collect()
+}
+
+/// Rewards consecutive characters and matches at the start of a word.
+fn score(query: &str, title: &str) -> Option<usize> {
+    let title = title.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + title[pos..].iter().position(|&t| t == c)?;
+        score += 1;
+        if found == 0 || !title[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if last.map(|last| last + 1 == found).unwrap_or(false) {
+            score += 2;
+        }
+        last = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+pub(crate) fn render(list: &Element, matches: &[&Action], active: usize, keymap: &Keymap) -> Result<(), JsValue> {
+    let document = list.owner_document().ok_or("no owner document found")?;
+    list.set_text_content(None);
+
+    for (index, action) in matches.iter().enumerate() {
+        let item = document.create_element("li")?;
+        item.set_class_name("app-palette__item");
+        item.set_attribute("data-action", action.name)?;
+
+        let title = document.create_element("span")?;
+        title.set_text_content(Some(action.title));
+        item.append_child(&title)?;
+
+        if let Some(key) = keymap.key(action.name) {
+            let kbd = document.create_element("kbd")?;
+            kbd.set_text_content(Some(key));
+            item.append_child(&kbd)?;
+        }
+
+        list.append_child(&item)?;
+        if index == active {
+            item.class_list().add_1("app-palette__item--active")?;
+            item.scroll_into_view_with_bool(false);
+        }
+    }
+    Ok(())
+}
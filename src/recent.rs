@@ -0,0 +1,139 @@
+use wasm_bindgen::{JsCast, JsValue};
+use js_sys::{Date, Reflect};
+use web_sys::{Element, IdbDatabase};
+
+use crate::{file, idb};
+use crate::sys::file_system_access::FileSystemFileHandle;
+
+const MAX_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    /// Store key. Files of the same name from different folders get their own entries.
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) size: f64,
+    pub(crate) timestamp: f64,
+    pub(crate) handle: Option<FileSystemFileHandle>,
+}
+
+impl Entry {
+    pub(crate) fn new(name: String, size: f64, handle: Option<FileSystemFileHandle>) -> Self {
+        let timestamp = Date::now();
+        Self {
+            id: format!("{}:{}", timestamp, name),
+            name,
+            size,
+            timestamp,
+            handle,
+        }
+    }
+
+    fn to_js(&self) -> JsValue {
+        js! {
+            "id" => &self.id,
+            "name" => &self.name,
+            "size" => self.size,
+            "timestamp" => self.timestamp,
+            "handle" => self.handle.as_ref().map(JsValue::from).unwrap_or(JsValue::NULL)
+        }.into()
+    }
+
+    fn from_js(value: &JsValue) -> Option<Self> {
+        #[allow(unused_unsafe)]
+        let (id, name, size, timestamp, handle) = unsafe {
+            (
+                Reflect::get(value, &"id".into()).ok().and_then(|id| id.as_string()),
+                Reflect::get(value, &"name".into()).ok()?.as_string()?,
+                Reflect::get(value, &"size".into()).ok()?.as_f64()?,
+                Reflect::get(value, &"timestamp".into()).ok()?.as_f64()?,
+                Reflect::get(value, &"handle".into()).ok().filter(|h| h.is_object()),
+            )
+        };
+        let handle = handle.map(JsCast::unchecked_into::<FileSystemFileHandle>);
+        // entries stored before ids were added are keyed by name
+        let id = id.unwrap_or_else(|| name.clone());
+        Some(Self { id, name, size, timestamp, handle })
+    }
+
+    /// Whether both entries refer to the same file. Entries without a handle only match by name.
+    async fn same_file(&self, other: &Entry) -> bool {
+        match (&self.handle, &other.handle) {
+            (Some(a), Some(b)) => file::same_entry(a, b).await,
+            (None, None) => self.name == other.name,
+            _ => false,
+        }
+    }
+}
+
+pub(crate) async fn load(db: &IdbDatabase) -> Result<Vec<Entry>, JsValue> {
+    let values = idb::get_all(db, idb::RECENT).await?;
+    let mut entries = values.iter().filter_map(|value| Entry::from_js(&value)).collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.timestamp.partial_cmp(&a.timestamp).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(entries)
+}
+
+pub(crate) async fn add(db: &IdbDatabase, mut entry: Entry) -> Result<(), JsValue> {
+    let mut others = vec![];
+    for e in load(db).await? {
+        if entry.same_file(&e).await {
+            entry.id = e.id;
+        } else {
+            others.push(e);
+        }
+    }
+    let expired = others.into_iter().skip(MAX_ENTRIES - 1).collect::<Vec<_>>();
+
+    let (transaction, store) = idb::transaction(db, idb::RECENT, true)?;
+    store.put_with_key(&entry.to_js(), &JsValue::from(&entry.id))?;
+    for e in expired {
+        store.delete(&JsValue::from(&e.id))?;
+    }
+    idb::complete(&transaction).await
+}
+
+fn format_size(size: f64) -> String {
+    match size {
+        s if s < 1024.0 => format!("{} B", s),
+        s if s < 1024.0 * 1024.0 => format!("{:.1} KB", s / 1024.0),
+        s => format!("{:.1} MB", s / 1024.0 / 1024.0),
+    }
+}
+
+pub(crate) fn render(list: &Element, entries: &[Entry]) -> Result<(), JsValue> {
+    let document = list.owner_document().ok_or("no owner document found")?;
+    list.set_text_content(None);
+
+    for entry in entries {
+        let item = document.create_element("a")?;
+        item.set_class_name("mdc-list-item");
+        item.set_attribute("tabindex", "0")?;
+        item.set_attribute("data-id", &entry.id)?;
+
+        let ripple = document.create_element("span")?;
+        ripple.set_class_name("mdc-list-item__ripple");
+        item.append_child(&ripple)?;
+
+        let icon = document.create_element("i")?;
+        icon.set_class_name("material-icons mdc-list-item__graphic");
+        icon.set_text_content(Some("description"));
+        item.append_child(&icon)?;
+
+        let text = document.create_element("span")?;
+        text.set_class_name("mdc-list-item__text");
+        let primary = document.create_element("span")?;
+        primary.set_class_name("mdc-list-item__primary-text");
+        primary.set_text_content(Some(&entry.name));
+        text.append_child(&primary)?;
+        let secondary = document.create_element("span")?;
+        secondary.set_class_name("mdc-list-item__secondary-text");
+        let date = Date::new(&JsValue::from(entry.timestamp));
+        let date = date.to_locale_string("default", &JsValue::UNDEFINED);
+        secondary.set_text_content(Some(&format!("{} / {}", format_size(entry.size), String::from(date))));
+        text.append_child(&secondary)?;
+        item.append_child(&text)?;
+
+        list.append_child(&item)?;
+    }
+    Ok(())
+}
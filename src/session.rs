@@ -0,0 +1,62 @@
+use wasm_bindgen::JsValue;
+use js_sys::Reflect;
+use web_sys::IdbDatabase;
+
+use crate::idb;
+
+#[derive(Debug)]
+pub(crate) struct Snapshot {
+    pub(crate) name: String,
+    pub(crate) text: String,
+    pub(crate) encoding: Option<String>,
+    pub(crate) header: bool,
+    pub(crate) delimiter: char,
+}
+
+impl Snapshot {
+    fn to_js(&self) -> JsValue {
+        js! {
+            "name" => &self.name,
+            "text" => &self.text,
+            "encoding" => self.encoding.as_deref().map(JsValue::from).unwrap_or(JsValue::NULL),
+            "dialect" => js! {
+                "header" => self.header,
+                "delimiter" => self.delimiter.to_string()
+            }
+        }.into()
+    }
+
+    fn from_js(value: &JsValue) -> Option<Self> {
+        #[allow(unused_unsafe)]
+        let (name, text, encoding, header, delimiter) = unsafe {
+            let dialect = Reflect::get(value, &"dialect".into()).ok()?;
+            (
+                Reflect::get(value, &"name".into()).ok()?.as_string()?,
+                Reflect::get(value, &"text".into()).ok()?.as_string()?,
+                Reflect::get(value, &"encoding".into()).ok()?.as_string(),
+                Reflect::get(&dialect, &"header".into()).ok()?.as_bool().unwrap_or(true),
+                Reflect::get(&dialect, &"delimiter".into()).ok()?.as_string()
+                    .and_then(|d| d.chars().next()).unwrap_or(','),
+            )
+        };
+        Some(Self { name, text, encoding, header, delimiter })
+    }
+}
+
+pub(crate) async fn save(db: &IdbDatabase, snapshots: &[Snapshot]) -> Result<(), JsValue> {
+    let (transaction, store) = idb::transaction(db, idb::SESSION, true)?;
+    store.clear()?;
+    for (key, snapshot) in snapshots.iter().enumerate() {
+        store.put_with_key(&snapshot.to_js(), &JsValue::from(key as u32))?;
+    }
+    idb::complete(&transaction).await
+}
+
+pub(crate) async fn load(db: &IdbDatabase) -> Result<Vec<Snapshot>, JsValue> {
+    let values = idb::get_all(db, idb::SESSION).await?;
+    Ok(values.iter().filter_map(|value| Snapshot::from_js(&value)).collect())
+}
+
+pub(crate) async fn clear(db: &IdbDatabase) -> Result<(), JsValue> {
+    save(db, &[]).await
+}
@@ -0,0 +1,58 @@
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+use crate::document::Document;
+
+pub(crate) fn render(status: &Element, doc: Option<&Document>) -> Result<(), JsValue> {
+    let doc = if let Some(doc) = doc {
+        doc
+    } else {
+        status.set_text_content(None);
+        return Ok(())
+    };
+    let csv = doc.grid().csv();
+    // keep the previous facts while an edit holds the csv
+    let csv = if let Some(csv) = csv.try_lock() {
+        csv
+    } else {
+        return Ok(())
+    };
+
+    let cell = match doc.grid().cursor(&csv)? {
+        Some((row, col)) => match csv.header(col).filter(|name| !name.is_empty()) {
+            Some(name) => format!("{}:{} {}", row + 1, col + 1, name),
+            None => format!("{}:{}", row + 1, col + 1),
+        },
+        None => "-".to_string(),
+    };
+    let delimiter = match csv.delimiter() {
+        ',' => "Comma".to_string(),
+        '\t' => "Tab".to_string(),
+        ';' => "Semicolon".to_string(),
+        c => c.to_string(),
+    };
+    let eol = match csv.eol() {
+        "\r\n" => "CRLF",
+        "\n" => "LF",
+        _ => "CR",
+    };
+    let items = [
+        format!("{} rows \u{00d7} {} columns", csv.rows(), csv.max_cols()),
+        cell,
+        doc.encoding().to_uppercase(),
+        delimiter,
+        eol.to_string(),
+        if csv.has_bom() { "BOM" } else { "No BOM" }.to_string(),
+        if doc.dirty() { "Modified" } else { "Saved" }.to_string(),
+    ];
+
+    let document = status.owner_document().ok_or("no owner document found")?;
+    status.set_text_content(None);
+    for (index, text) in items.iter().enumerate() {
+        let item = document.create_element("span")?;
+        item.set_class_name(if index == 1 { "app-status__item app-status__item--fill" } else { "app-status__item" });
+        item.set_text_content(Some(text));
+        status.append_child(&item)?;
+    }
+    Ok(())
+}
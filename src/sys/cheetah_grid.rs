@@ -1,12 +1,30 @@
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::closure::Closure;
-use js_sys::Object;
+use js_sys::{Array, Function, Object};
 
 #[wasm_bindgen(module = "cheetah-grid")]
 extern "C" {
     #[wasm_bindgen(js_namespace = ["ListGrid", "EVENT_TYPE"])]
     pub(crate) static CHANGED_VALUE: String;
 
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["ListGrid", "EVENT_TYPE"])]
+    pub(crate) static CONTEXTMENU_CELL: String;
+
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["ListGrid", "EVENT_TYPE"])]
+    pub(crate) static DBLCLICK_CELL: String;
+
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["ListGrid", "EVENT_TYPE"])]
+    pub(crate) static SELECTED_CELL: String;
+
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["ListGrid", "EVENT_TYPE"])]
+    pub(crate) static MOUSEDOWN_CELL: String;
+
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["ListGrid", "EVENT_TYPE"])]
+    pub(crate) static MOUSEUP_CELL: String;
+
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["ListGrid", "EVENT_TYPE"])]
+    pub(crate) static RESIZE_COLUMN: String;
+
     #[derive(Debug, Clone)]
     pub(crate) type ListGrid;
 
@@ -19,16 +37,71 @@ extern "C" {
     #[wasm_bindgen(method, catch)]
     pub(crate) fn invalidate(this: &ListGrid) -> Result<(), JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name = "updateSize")]
+    pub(crate) fn update_size(this: &ListGrid) -> Result<(), JsValue>;
+
     #[wasm_bindgen(method, getter, catch, js_name = "dataSource")]
     pub(crate) fn data_source(this: &ListGrid) -> Result<CachedDataSource, JsValue>;
 
     #[wasm_bindgen(method, getter, catch)]
-    pub(crate) fn selection(this: &ListGrid) -> Result<Object, JsValue>;
+    pub(crate) fn selection(this: &ListGrid) -> Result<Selection, JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "makeVisibleCell")]
+    pub(crate) fn make_visible_cell(this: &ListGrid, col: usize, row: usize) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "focusCell")]
+    pub(crate) fn focus_cell(this: &ListGrid, col: usize, row: usize) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "getCellRect")]
+    pub(crate) fn get_cell_rect(this: &ListGrid, col: usize, row: usize) -> Result<Object, JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "getColWidth")]
+    pub(crate) fn get_col_width(this: &ListGrid, col: usize) -> Result<f64, JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "setColWidth")]
+    pub(crate) fn set_col_width(this: &ListGrid, col: usize, width: usize) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, setter, js_name = "frozenColCount")]
+    pub(crate) fn set_frozen_col_count(this: &ListGrid, val: usize);
+
+    #[wasm_bindgen(method, setter, js_name = "scrollTop")]
+    pub(crate) fn set_scroll_top(this: &ListGrid, val: f64);
+
+    #[wasm_bindgen(method, catch, js_name = "getCellRelativeRect")]
+    pub(crate) fn get_cell_relative_rect(this: &ListGrid, col: usize, row: usize) -> Result<Object, JsValue>;
+
+    #[wasm_bindgen(method, setter, catch)]
+    pub(crate) fn set_header(this: &ListGrid, header: &Array) -> Result<(), JsValue>;
+
+    #[derive(Debug, Clone)]
+    pub(crate) type Selection;
+
+    #[wasm_bindgen(method, getter)]
+    pub(crate) fn select(this: &Selection) -> Object;
+
+    #[wasm_bindgen(method, setter)]
+    pub(crate) fn set_select(this: &Selection, cell: &Object);
+
+    #[wasm_bindgen(method, getter)]
+    pub(crate) fn range(this: &Selection) -> Object;
+
+    #[derive(Debug, Clone)]
+    pub(crate) type CheckEditor;
 
-    pub(crate) type InlineInputEditor;
+    #[wasm_bindgen(constructor, catch, js_namespace = ["columns", "action"])]
+    pub(crate) fn new() -> Result<CheckEditor, JsValue>;
+
+    #[wasm_bindgen(method, setter, js_name = "readOnly")]
+    pub(crate) fn set_read_only(this: &CheckEditor, val: bool);
+
+    #[derive(Debug, Clone)]
+    pub(crate) type InlineMenuEditor;
 
     #[wasm_bindgen(constructor, catch, js_namespace = ["columns", "action"])]
-    pub(crate) fn new() -> Result<InlineInputEditor, JsValue>;
+    pub(crate) fn new(opt: Object) -> Result<InlineMenuEditor, JsValue>;
+
+    #[wasm_bindgen(method, setter, js_name = "readOnly")]
+    pub(crate) fn set_read_only(this: &InlineMenuEditor, val: bool);
 
     pub(crate) type CachedDataSource;
 
@@ -45,3 +118,42 @@ extern "C" {
     pub(crate) fn set_length(this: &CachedDataSource, val: usize) -> Result<(), JsValue>;
 
 }
+
+#[wasm_bindgen(module = "/js/editor.js")]
+extern "C" {
+    #[derive(Debug, Clone)]
+    pub(crate) type CellEditor;
+
+    #[wasm_bindgen(constructor, catch)]
+    pub(crate) fn new(opt: Object) -> Result<CellEditor, JsValue>;
+
+    #[wasm_bindgen(method, setter, js_name = "readOnly")]
+    pub(crate) fn set_read_only(this: &CellEditor, val: bool);
+
+    #[wasm_bindgen(method, getter, js_name = "openText")]
+    pub(crate) fn open_text(this: &CellEditor) -> JsValue;
+
+    #[wasm_bindgen(method, setter, js_name = "openText")]
+    pub(crate) fn set_open_text(this: &CellEditor, val: &Function);
+}
+
+#[wasm_bindgen(module = "/js/highlight.js")]
+extern "C" {
+    #[derive(Debug, Clone)]
+    pub(crate) type HighlightColumn;
+
+    #[wasm_bindgen(constructor, catch)]
+    pub(crate) fn new() -> Result<HighlightColumn, JsValue>;
+
+    #[wasm_bindgen(method, setter)]
+    pub(crate) fn set_query(this: &HighlightColumn, query: &str);
+
+    #[derive(Debug, Clone)]
+    pub(crate) type HighlightNumberColumn;
+
+    #[wasm_bindgen(constructor, catch)]
+    pub(crate) fn new() -> Result<HighlightNumberColumn, JsValue>;
+
+    #[wasm_bindgen(method, setter)]
+    pub(crate) fn set_query(this: &HighlightNumberColumn, query: &str);
+}
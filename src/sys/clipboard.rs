@@ -0,0 +1,11 @@
+use wasm_bindgen::prelude::*;
+use js_sys::Promise;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(catch, js_namespace = ["navigator", "clipboard"], js_name = "readText")]
+    pub(crate) fn read_text() -> Result<Promise, JsValue>;
+
+    #[wasm_bindgen(catch, js_namespace = ["navigator", "clipboard"], js_name = "writeText")]
+    pub(crate) fn write_text(data: &str) -> Result<Promise, JsValue>;
+}
@@ -0,0 +1,38 @@
+use wasm_bindgen::prelude::*;
+use js_sys::{Object, Promise};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(catch, js_name = "showOpenFilePicker")]
+    pub(crate) fn show_open_file_picker(options: &Object) -> Result<Promise, JsValue>;
+
+    #[wasm_bindgen(catch, js_name = "showSaveFilePicker")]
+    pub(crate) fn show_save_file_picker(options: &Object) -> Result<Promise, JsValue>;
+
+    #[derive(Debug, Clone)]
+    pub(crate) type FileSystemFileHandle;
+
+    #[wasm_bindgen(method, getter)]
+    pub(crate) fn name(this: &FileSystemFileHandle) -> String;
+
+    #[wasm_bindgen(method, js_name = "requestPermission")]
+    pub(crate) fn request_permission(this: &FileSystemFileHandle, descriptor: &Object) -> Promise;
+
+    #[wasm_bindgen(method, js_name = "getFile")]
+    pub(crate) fn get_file(this: &FileSystemFileHandle) -> Promise;
+
+    #[wasm_bindgen(method, js_name = "isSameEntry")]
+    pub(crate) fn is_same_entry(this: &FileSystemFileHandle, other: &FileSystemFileHandle) -> Promise;
+
+    #[wasm_bindgen(method, js_name = "createWritable")]
+    pub(crate) fn create_writable(this: &FileSystemFileHandle) -> Promise;
+
+    #[derive(Debug, Clone)]
+    pub(crate) type FileSystemWritableFileStream;
+
+    #[wasm_bindgen(method)]
+    pub(crate) fn write(this: &FileSystemWritableFileStream, data: &JsValue) -> Promise;
+
+    #[wasm_bindgen(method)]
+    pub(crate) fn close(this: &FileSystemWritableFileStream) -> Promise;
+}
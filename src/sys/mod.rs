@@ -1,2 +1,4 @@
 pub(crate) mod cheetah_grid;
 pub(crate) mod material;
+pub(crate) mod file_system_access;
+pub(crate) mod clipboard;
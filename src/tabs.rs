@@ -0,0 +1,37 @@
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+use crate::document::Document;
+
+pub(crate) fn render(tabs: &Element, documents: &[Document], active: Option<usize>) -> Result<(), JsValue> {
+    let document = tabs.owner_document().ok_or("no owner document found")?;
+    tabs.set_text_content(None);
+
+    for (index, doc) in documents.iter().enumerate() {
+        let tab = document.create_element("button")?;
+        tab.set_class_name("app-tab");
+        if Some(index) == active {
+            tab.class_list().add_1("app-tab--active")?;
+        }
+        tab.set_attribute("data-index", &index.to_string())?;
+        tab.set_attribute("title", doc.name())?;
+
+        let name = document.create_element("span")?;
+        name.set_class_name("app-tab__name");
+        if doc.dirty() {
+            name.set_text_content(Some(&format!("{} *", doc.name())));
+        } else {
+            name.set_text_content(Some(doc.name()));
+        }
+        tab.append_child(&name)?;
+
+        let close = document.create_element("i")?;
+        close.set_class_name("material-icons app-tab__close");
+        close.set_attribute("data-action", "close")?;
+        close.set_text_content(Some("close"));
+        tab.append_child(&close)?;
+
+        tabs.append_child(&tab)?;
+    }
+    Ok(())
+}